@@ -0,0 +1,579 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use kas::event::config::Shortcuts;
+use kas::event::{Command, ModifiersState, VirtualKeyCode as VK};
+use log::{error, warn};
+
+/// Player actions which keys can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PlayerCommand {
+    PlayPause,
+    Stop,
+    SeekBackward,
+    SeekForward,
+    SeekBackwardLong,
+    SeekForwardLong,
+    StepBackward,
+    StepForward,
+    Slower,
+    Faster,
+    AudioEarlier,
+    AudioLater,
+    SubtitleEarlier,
+    SubtitleLater,
+    MarkIn,
+    MarkOut,
+    VolumeDown,
+    VolumeUp,
+    ToggleMute,
+    ToggleFullscreen,
+    ExitFullscreen,
+    PlaylistPrevious,
+    PlaylistNext,
+    ShowShortcuts,
+}
+
+impl PlayerCommand {
+    const ALL: &'static [(PlayerCommand, &'static str, &'static str)] = &[
+        (PlayerCommand::PlayPause, "play-pause", "play / pause"),
+        (PlayerCommand::Stop, "stop", "stop"),
+        (
+            PlayerCommand::SeekBackward,
+            "seek-backward",
+            "back 5 seconds",
+        ),
+        (
+            PlayerCommand::SeekForward,
+            "seek-forward",
+            "forward 5 seconds",
+        ),
+        (
+            PlayerCommand::SeekBackwardLong,
+            "seek-backward-long",
+            "back 1 minute",
+        ),
+        (
+            PlayerCommand::SeekForwardLong,
+            "seek-forward-long",
+            "forward 1 minute",
+        ),
+        (
+            PlayerCommand::StepBackward,
+            "step-backward",
+            "previous frame",
+        ),
+        (PlayerCommand::StepForward, "step-forward", "next frame"),
+        (PlayerCommand::Slower, "slower", "slower"),
+        (PlayerCommand::Faster, "faster", "faster"),
+        (
+            PlayerCommand::AudioEarlier,
+            "audio-earlier",
+            "audio earlier",
+        ),
+        (PlayerCommand::AudioLater, "audio-later", "audio later"),
+        (
+            PlayerCommand::SubtitleEarlier,
+            "subtitle-earlier",
+            "subtitles earlier",
+        ),
+        (
+            PlayerCommand::SubtitleLater,
+            "subtitle-later",
+            "subtitles later",
+        ),
+        (PlayerCommand::MarkIn, "mark-in", "mark the in point"),
+        (PlayerCommand::MarkOut, "mark-out", "mark the out point"),
+        (PlayerCommand::VolumeDown, "volume-down", "volume down"),
+        (PlayerCommand::VolumeUp, "volume-up", "volume up"),
+        (PlayerCommand::ToggleMute, "toggle-mute", "mute / unmute"),
+        (
+            PlayerCommand::ToggleFullscreen,
+            "toggle-fullscreen",
+            "fullscreen",
+        ),
+        (
+            PlayerCommand::ExitFullscreen,
+            "exit-fullscreen",
+            "leave fullscreen",
+        ),
+        (
+            PlayerCommand::PlaylistPrevious,
+            "playlist-previous",
+            "previous in playlist",
+        ),
+        (
+            PlayerCommand::PlaylistNext,
+            "playlist-next",
+            "next in playlist",
+        ),
+        (PlayerCommand::ShowShortcuts, "show-shortcuts", "this list"),
+    ];
+
+    fn entry(self) -> &'static (PlayerCommand, &'static str, &'static str) {
+        PlayerCommand::ALL
+            .iter()
+            .find(|e| e.0 == self)
+            .expect("every command is listed")
+    }
+
+    /// Name of the command in the bindings file.
+    pub fn name(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// What the command does, for the shortcuts dialog.
+    pub fn description(self) -> &'static str {
+        self.entry().2
+    }
+}
+
+impl FromStr for PlayerCommand {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PlayerCommand::ALL
+            .iter()
+            .find(|e| e.1 == s)
+            .map(|e| e.0)
+            .ok_or(())
+    }
+}
+
+/// Names of the keys in chords, other than letters and `F1` to `F12`.
+const KEY_NAMES: &[(&str, VK)] = &[
+    ("Space", VK::Space),
+    ("Escape", VK::Escape),
+    ("Enter", VK::Return),
+    ("Tab", VK::Tab),
+    ("Backspace", VK::Back),
+    ("Insert", VK::Insert),
+    ("Delete", VK::Delete),
+    ("Home", VK::Home),
+    ("End", VK::End),
+    ("PageUp", VK::PageUp),
+    ("PageDown", VK::PageDown),
+    ("Left", VK::Left),
+    ("Right", VK::Right),
+    ("Up", VK::Up),
+    ("Down", VK::Down),
+    ("0", VK::Key0),
+    ("1", VK::Key1),
+    ("2", VK::Key2),
+    ("3", VK::Key3),
+    ("4", VK::Key4),
+    ("5", VK::Key5),
+    ("6", VK::Key6),
+    ("7", VK::Key7),
+    ("8", VK::Key8),
+    ("9", VK::Key9),
+    ("[", VK::LBracket),
+    ("]", VK::RBracket),
+    (",", VK::Comma),
+    (".", VK::Period),
+    ("-", VK::Minus),
+    ("=", VK::Equals),
+    ("/", VK::Slash),
+    (";", VK::Semicolon),
+    ("'", VK::Apostrophe),
+    ("`", VK::Grave),
+    ("\\", VK::Backslash),
+];
+
+const LETTERS: &[VK] = &[
+    VK::A,
+    VK::B,
+    VK::C,
+    VK::D,
+    VK::E,
+    VK::F,
+    VK::G,
+    VK::H,
+    VK::I,
+    VK::J,
+    VK::K,
+    VK::L,
+    VK::M,
+    VK::N,
+    VK::O,
+    VK::P,
+    VK::Q,
+    VK::R,
+    VK::S,
+    VK::T,
+    VK::U,
+    VK::V,
+    VK::W,
+    VK::X,
+    VK::Y,
+    VK::Z,
+];
+
+const FUNCTION_KEYS: &[VK] = &[
+    VK::F1,
+    VK::F2,
+    VK::F3,
+    VK::F4,
+    VK::F5,
+    VK::F6,
+    VK::F7,
+    VK::F8,
+    VK::F9,
+    VK::F10,
+    VK::F11,
+    VK::F12,
+];
+
+const MODIFIER_NAMES: &[(&str, ModifiersState)] = &[
+    ("Ctrl", ModifiersState::CTRL),
+    ("Shift", ModifiersState::SHIFT),
+    ("Alt", ModifiersState::ALT),
+    ("Super", ModifiersState::LOGO),
+];
+
+/// A key with its modifiers, written like `Ctrl+Right` or `]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: ModifiersState,
+    pub key: VK,
+}
+
+/// The keys which chords can be written with.
+fn named_keys() -> impl Iterator<Item = VK> {
+    let names = KEY_NAMES.iter().map(|(_, key)| *key);
+    names
+        .chain(LETTERS.iter().copied())
+        .chain(FUNCTION_KEYS.iter().copied())
+}
+
+impl Chord {
+    const fn key(key: VK) -> Self {
+        Chord {
+            modifiers: ModifiersState::empty(),
+            key,
+        }
+    }
+
+    /// Whether kas reports the key itself, as an accelerator key.
+    ///
+    /// That is without modifiers, `Main` enabling alt-bypass, or with Alt alone.
+    pub fn is_accel(&self) -> bool {
+        self.modifiers.is_empty() || self.modifiers == ModifiersState::ALT
+    }
+
+    /// The key kas sent `cmd` for, with `modifiers` held.
+    ///
+    /// kas keeps only the command of the other chords, so the key is known when no other key
+    /// gives the same command, like Ctrl+Tab and Ctrl+PageDown do.
+    fn from_command(
+        modifiers: ModifiersState,
+        cmd: Command,
+        shortcuts: &Shortcuts,
+    ) -> Option<Self> {
+        let mut keys = named_keys().filter(|key| shortcuts.get(modifiers, *key) == Some(cmd));
+        match (keys.next(), keys.next()) {
+            (Some(key), None) => Some(Chord { modifiers, key }),
+            _ => None,
+        }
+    }
+
+    /// Whether `Main` can tell the chord from the others when it is pressed.
+    fn is_reported(&self, shortcuts: &Shortcuts) -> bool {
+        if self.is_accel() {
+            return true;
+        }
+        let Some(cmd) = shortcuts.get(self.modifiers, self.key) else {
+            return false;
+        };
+        Chord::from_command(self.modifiers, cmd, shortcuts) == Some(*self)
+    }
+}
+
+impl FromStr for Chord {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, name) = s.rsplit_once('+').unwrap_or(("", s));
+        let mut modifiers = ModifiersState::empty();
+        for m in prefix.split('+').filter(|m| !m.is_empty()) {
+            let (_, state) = MODIFIER_NAMES
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(m))
+                .ok_or(())?;
+            modifiers |= *state;
+        }
+        let key =
+            if let Some((_, key)) = KEY_NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+                *key
+            } else if let [c] = name.as_bytes() {
+                match c.to_ascii_uppercase() {
+                    c @ b'A'..=b'Z' => LETTERS[(c - b'A') as usize],
+                    _ => return Err(()),
+                }
+            } else {
+                let n: usize = name
+                    .strip_prefix(['F', 'f'])
+                    .and_then(|n| n.parse().ok())
+                    .ok_or(())?;
+                *FUNCTION_KEYS.get(n.wrapping_sub(1)).ok_or(())?
+            };
+        Ok(Chord { modifiers, key })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, state) in MODIFIER_NAMES {
+            if self.modifiers.contains(*state) {
+                write!(f, "{name}+")?;
+            }
+        }
+        if let Some((name, _)) = KEY_NAMES.iter().find(|(_, k)| *k == self.key) {
+            write!(f, "{name}")
+        } else if let Some(i) = LETTERS.iter().position(|k| *k == self.key) {
+            write!(f, "{}", (b'A' + i as u8) as char)
+        } else if let Some(i) = FUNCTION_KEYS.iter().position(|k| *k == self.key) {
+            write!(f, "F{}", i + 1)
+        } else {
+            write!(f, "{:?}", self.key)
+        }
+    }
+}
+
+const DEFAULTS: &[(Chord, PlayerCommand)] = &[
+    (Chord::key(VK::Space), PlayerCommand::PlayPause),
+    (Chord::key(VK::Left), PlayerCommand::SeekBackward),
+    (Chord::key(VK::Right), PlayerCommand::SeekForward),
+    (Chord::key(VK::Down), PlayerCommand::SeekBackwardLong),
+    (Chord::key(VK::Up), PlayerCommand::SeekForwardLong),
+    (Chord::key(VK::F), PlayerCommand::ToggleFullscreen),
+    (Chord::key(VK::F11), PlayerCommand::ToggleFullscreen),
+    (Chord::key(VK::Escape), PlayerCommand::ExitFullscreen),
+    (Chord::key(VK::M), PlayerCommand::ToggleMute),
+    (Chord::key(VK::LBracket), PlayerCommand::Slower),
+    (Chord::key(VK::RBracket), PlayerCommand::Faster),
+    (Chord::key(VK::Comma), PlayerCommand::StepBackward),
+    (Chord::key(VK::Period), PlayerCommand::StepForward),
+    (Chord::key(VK::Minus), PlayerCommand::AudioEarlier),
+    (Chord::key(VK::Equals), PlayerCommand::AudioLater),
+    (Chord::key(VK::Z), PlayerCommand::SubtitleEarlier),
+    (Chord::key(VK::X), PlayerCommand::SubtitleLater),
+    (Chord::key(VK::I), PlayerCommand::MarkIn),
+    (Chord::key(VK::O), PlayerCommand::MarkOut),
+    (Chord::key(VK::F1), PlayerCommand::ShowShortcuts),
+];
+
+/// The key chords bound to player commands.
+///
+/// The defaults are amended by `bindings.toml` under the XDG config directory,
+/// whose lines are like `"Ctrl+Right" = "playlist-next"`; the command `none` unbinds a chord.
+///
+/// Chords with Ctrl, Shift or Super can only be bound when kas has a command for them,
+/// which no other key with the same modifiers gives.
+#[derive(Debug, Default)]
+pub struct Bindings {
+    map: HashMap<Chord, PlayerCommand>,
+}
+
+impl Bindings {
+    pub fn load() -> Self {
+        let mut map: HashMap<_, _> = DEFAULTS.iter().copied().collect();
+        let path = dirs::config_dir().map(|d| d.join("kas-gstreamer-etude").join("bindings.toml"));
+        if let Some(path) = path {
+            Self::load_file(&path, &mut map);
+        }
+        Self::new(map, &Shortcuts::platform_defaults())
+    }
+
+    fn new(map: HashMap<Chord, PlayerCommand>, shortcuts: &Shortcuts) -> Self {
+        let mut bindings = Bindings::default();
+        for (chord, command) in map {
+            if chord.is_reported(shortcuts) {
+                bindings.map.insert(chord, command);
+            } else {
+                warn!(
+                    "{chord} cannot be bound to {}: kas tells it from no other key",
+                    command.name()
+                );
+            }
+        }
+        bindings
+    }
+
+    fn load_file(path: &Path, map: &mut HashMap<Chord, PlayerCommand>) {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return;
+        };
+        let table: HashMap<String, String> = match toml::from_str(&text) {
+            Ok(table) => table,
+            Err(e) => {
+                error!("{}: {}", path.display(), e);
+                return;
+            }
+        };
+        for (chord, command) in table {
+            let Ok(chord) = chord.parse::<Chord>() else {
+                warn!("{}: unknown key {chord:?}", path.display());
+                continue;
+            };
+            if command == "none" {
+                map.remove(&chord);
+            } else if let Ok(command) = command.parse() {
+                map.insert(chord, command);
+            } else {
+                warn!("{}: unknown command {command:?}", path.display());
+            }
+        }
+    }
+
+    /// The player command bound to a chord.
+    pub fn get(&self, chord: Chord) -> Option<PlayerCommand> {
+        self.map.get(&chord).copied()
+    }
+
+    /// The player command of the chord kas sent `cmd` for, with `modifiers` held.
+    ///
+    /// Accelerator chords are left out, their keys being reported as such.
+    pub fn get_command(
+        &self,
+        modifiers: ModifiersState,
+        cmd: Command,
+        shortcuts: &Shortcuts,
+    ) -> Option<PlayerCommand> {
+        let chord =
+            Chord::from_command(modifiers, cmd, shortcuts).filter(|chord| !chord.is_accel())?;
+        self.get(chord)
+    }
+
+    /// The keys of the chords reported as accelerator keys.
+    pub fn accel_keys(&self) -> Vec<VK> {
+        let mut keys: Vec<VK> = self
+            .map
+            .keys()
+            .filter(|c| c.is_accel())
+            .map(|c| c.key)
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// The bindings in the order of the commands, then of the chords.
+    pub fn list(&self) -> Vec<(Chord, PlayerCommand)> {
+        let mut list: Vec<_> = self.map.iter().map(|(c, p)| (*c, *p)).collect();
+        list.sort_by_key(|(chord, command)| (*command, chord.to_string()));
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(s: &str) -> Chord {
+        s.parse().unwrap_or_else(|_| panic!("{s:?} should parse"))
+    }
+
+    #[test]
+    fn chord_round_trip() {
+        let names = [
+            "Space",
+            "]",
+            "=",
+            "F",
+            "F11",
+            "Alt+X",
+            "Ctrl+Right",
+            "Shift+N",
+            "Ctrl+Shift+Tab",
+            "Super+PageDown",
+        ];
+        for s in names {
+            assert_eq!(chord(s).to_string(), s);
+        }
+        let modifiers = [
+            ModifiersState::empty(),
+            ModifiersState::CTRL,
+            ModifiersState::ALT,
+            ModifiersState::SHIFT | ModifiersState::LOGO,
+            ModifiersState::CTRL
+                | ModifiersState::SHIFT
+                | ModifiersState::ALT
+                | ModifiersState::LOGO,
+        ];
+        for modifiers in modifiers {
+            for key in named_keys() {
+                let c = Chord { modifiers, key };
+                assert_eq!(chord(&c.to_string()), c);
+            }
+        }
+    }
+
+    #[test]
+    fn chord_spelling() {
+        assert_eq!(chord("ctrl+shift+a"), chord("Ctrl+Shift+A"));
+        assert_eq!(chord("Shift+Ctrl+A").to_string(), "Ctrl+Shift+A");
+        assert_eq!(chord("f1").key, VK::F1);
+        assert_eq!(
+            chord("Alt+x"),
+            Chord {
+                modifiers: ModifiersState::ALT,
+                key: VK::X
+            }
+        );
+        assert_ne!(chord("Alt+X"), chord("X"));
+        for s in ["", "Ctrl+", "Hyper+A", "F0", "F13", "AB", "Ctrl+Shift"] {
+            assert!(s.parse::<Chord>().is_err(), "{s:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn chords_stay_distinct() {
+        let shortcuts = Shortcuts::platform_defaults();
+        let map = [
+            (chord("X"), PlayerCommand::Stop),
+            (chord("Alt+X"), PlayerCommand::ToggleMute),
+            (chord("Ctrl+Tab"), PlayerCommand::PlaylistNext),
+            (chord("Shift+N"), PlayerCommand::PlaylistNext),
+            (chord("Ctrl+Right"), PlayerCommand::SeekForwardLong),
+            (chord("Ctrl+Shift+Right"), PlayerCommand::PlaylistNext),
+        ];
+        let bindings = Bindings::new(map.into_iter().collect(), &shortcuts);
+        assert_eq!(bindings.get(chord("X")), Some(PlayerCommand::Stop));
+        assert_eq!(
+            bindings.get(chord("Alt+X")),
+            Some(PlayerCommand::ToggleMute)
+        );
+        assert_eq!(bindings.accel_keys(), vec![VK::X]);
+        // the same command as Ctrl+PageDown, and no command at all
+        assert_eq!(bindings.get(chord("Ctrl+Tab")), None);
+        assert_eq!(bindings.get(chord("Shift+N")), None);
+
+        let get = |s: &str| {
+            let c = chord(s);
+            let cmd = shortcuts.get(c.modifiers, c.key).unwrap();
+            bindings.get_command(c.modifiers, cmd, &shortcuts)
+        };
+        assert_eq!(get("Ctrl+Right"), Some(PlayerCommand::SeekForwardLong));
+        assert_eq!(get("Ctrl+Shift+Right"), Some(PlayerCommand::PlaylistNext));
+        assert_eq!(get("Ctrl+PageDown"), None);
+        // reported as the key itself
+        assert_eq!(get("Right"), None);
+    }
+
+    #[test]
+    fn defaults_are_bound() {
+        let bindings = Bindings::new(
+            DEFAULTS.iter().copied().collect(),
+            &Shortcuts::platform_defaults(),
+        );
+        assert_eq!(bindings.list().len(), DEFAULTS.len());
+        assert_eq!(
+            bindings.get(chord("F")),
+            Some(PlayerCommand::ToggleFullscreen)
+        );
+        assert_eq!(bindings.get(chord("Space")), Some(PlayerCommand::PlayPause));
+    }
+}
@@ -0,0 +1,71 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use gstreamer as gst;
+use gstreamer_base as gst_base;
+use log::{error, info};
+
+/// Bytes read from the head of a file for typefinding.
+const TYPEFIND_SIZE: u64 = 64 * 1024;
+
+/// Containers whose caps are not under `video/` or `audio/`.
+const MEDIA_CAPS: &[&str] = &[
+    "application/ogg",
+    "application/x-id3",
+    "application/x-apetag",
+    "application/mxf",
+    "application/vnd.rn-realmedia",
+];
+
+/// Whether GStreamer recognizes the content of `path` as audio or video, whatever its extension.
+pub fn is_playable(path: &Path) -> bool {
+    let mut data = Vec::new();
+    let read = std::fs::File::open(path).and_then(|f| f.take(TYPEFIND_SIZE).read_to_end(&mut data));
+    if let Err(e) = read {
+        error!("{}: {}", path.display(), e);
+        return false;
+    }
+    let caps = gst_base::functions::type_find_helper_for_data(None::<&gst::Object>, &data);
+    let Ok((caps, _probability)) = caps else {
+        return false;
+    };
+    let Some(s) = caps.structure(0) else {
+        return false;
+    };
+    let name = s.name();
+    name.starts_with("video/") || name.starts_with("audio/") || MEDIA_CAPS.contains(&name.as_str())
+}
+
+fn scan_into(dir: &Path, found: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("{}: {}", dir.display(), e);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // file_type does not follow symlinks, which keeps loops out of the scan
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => scan_into(&path, found),
+            Ok(t) if (t.is_file() || t.is_symlink() && path.is_file()) && is_playable(&path) => {
+                found.push(path);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Playable files under `dir` and its subdirectories, in natural order of their paths.
+pub fn scan(dir: &Path) -> Vec<PathBuf> {
+    if let Err(e) = gst::init() {
+        error!("{:?}", e);
+        return Vec::new();
+    }
+    let mut found = Vec::new();
+    scan_into(dir, &mut found);
+    found.sort_by(|a, b| natord::compare(&a.to_string_lossy(), &b.to_string_lossy()));
+    info!("{} playable files in {}", found.len(), dir.display());
+    found
+}
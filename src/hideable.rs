@@ -0,0 +1,65 @@
+use kas::prelude::*;
+
+impl_scope! {
+    /// Wraps a widget which can be hidden, taking no room and drawing nothing.
+    #[autoimpl(Deref, DerefMut using self.inner)]
+    #[widget{
+        layout = self.inner;
+    }]
+    #[derive(Debug)]
+    pub struct Hideable<W: Widget> {
+        core: widget_core!(),
+        #[widget] inner: W,
+        hidden: bool,
+    }
+
+    impl Self {
+        pub fn new(inner: W) -> Self {
+            Hideable {
+                core: Default::default(),
+                inner,
+                hidden: false,
+            }
+        }
+
+        /// Hide or show the widget. Its accelerator keys are disabled while hidden.
+        pub fn set_hidden(&mut self, mgr: &mut EventMgr, hidden: bool) {
+            if hidden == self.hidden {
+                return;
+            }
+            self.hidden = hidden;
+            mgr.set_disabled(self.inner.id(), hidden);
+            *mgr |= Action::RESIZE;
+        }
+    }
+
+    impl Layout for Self {
+        fn size_rules(&mut self, size_mgr: SizeMgr, axis: AxisInfo) -> SizeRules {
+            // solved anyway, as the rules must be before set_rect
+            let rules = self.inner.size_rules(size_mgr, axis);
+            if self.hidden {
+                SizeRules::EMPTY
+            } else {
+                rules
+            }
+        }
+
+        fn set_rect(&mut self, mgr: &mut ConfigMgr, rect: Rect) {
+            self.core.rect = rect;
+            self.inner.set_rect(mgr, rect);
+        }
+
+        fn find_id(&mut self, coord: Coord) -> Option<WidgetId> {
+            if self.hidden {
+                return None;
+            }
+            self.inner.find_id(coord)
+        }
+
+        fn draw(&mut self, mut draw: DrawMgr) {
+            if !self.hidden {
+                draw.recurse(&mut self.inner);
+            }
+        }
+    }
+}
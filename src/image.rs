@@ -1,18 +1,84 @@
+use kas::event::ScrollDelta;
 use kas::prelude::*;
 use kas::resvg::{tiny_skia, tiny_skia::Pixmap, Canvas, CanvasProgram};
-//use log::{error, info};
+use log::error;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc, Condvar, Mutex,
 };
+use std::time::Duration;
 
-#[derive(Debug, Clone)]
+use super::subtitle;
+
+pub use kas::resvg::tiny_skia::FilterQuality;
+
+/// Zoom factor of one wheel step.
+const ZOOM_STEP: f32 = 1.25;
+const ZOOM_RANGE: std::ops::RangeInclusive<f32> = 0.125..=64.0;
+/// Pixels of a smooth scroll counted as one wheel step.
+const PIXELS_PER_STEP: f32 = 50.0;
+
+/// How the frames are laid in the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayMode {
+    /// Scaled to the largest size showing the whole frame, letterboxed.
+    #[default]
+    Fit,
+    /// Scaled to cover the whole canvas, cropping the frame.
+    Fill,
+    /// Scaled to the canvas, ignoring the aspect ratio.
+    Stretch,
+    /// One frame pixel for one canvas pixel, centered.
+    Original,
+}
+
+impl DisplayMode {
+    /// Scale factors and offset placing a `frame` sized picture in a `canvas` sized one.
+    fn place(self, frame: (f32, f32), canvas: (f32, f32)) -> tiny_skia::Transform {
+        let (sx, sy) = (canvas.0 / frame.0, canvas.1 / frame.1);
+        let (sx, sy) = match self {
+            DisplayMode::Fit => (sx.min(sy), sx.min(sy)),
+            DisplayMode::Fill => (sx.max(sy), sx.max(sy)),
+            DisplayMode::Stretch => (sx, sy),
+            DisplayMode::Original => (1.0, 1.0),
+        };
+        let dx = (canvas.0 - frame.0 * sx) / 2.0;
+        let dy = (canvas.1 - frame.1 * sy) / 2.0;
+        tiny_skia::Transform::from_row(sx, 0.0, 0.0, sy, dx.round(), dy.round())
+    }
+}
+
+#[derive(Debug)]
 struct ImageProgramData {
     pixmap: Option<Pixmap>,
+    subtitles: Option<subtitle::Subtitles>,
+    // loaded with the first subtitles
+    renderer: Option<subtitle::Renderer>,
+    style: subtitle::Style,
+    position: Option<Duration>,
+    // cues shown at `position`
+    active: Vec<usize>,
+    mode: DisplayMode,
+    quality: FilterQuality,
+    // view over the placed frame, kept from frame to frame
+    zoom: f32,
+    pan: (f32, f32),
 }
 impl ImageProgramData {
     pub fn new(name: &str) -> (ImageProgramDrawer, ImageProgramSetter) {
-        let data = Self { pixmap: None };
+        let data = Self {
+            pixmap: None,
+            subtitles: None,
+            renderer: None,
+            style: subtitle::Style::default(),
+            position: None,
+            active: vec![],
+            mode: DisplayMode::default(),
+            quality: FilterQuality::Bilinear,
+            zoom: 1.0,
+            pan: (0.0, 0.0),
+        };
         let arc = Arc::new((Mutex::new(data), Condvar::new()));
         let need_redraw = Arc::new(AtomicBool::new(true));
         (
@@ -55,6 +121,87 @@ impl ImageProgramSetter {
         self.need_redraw.store(true, Ordering::Relaxed);
         Some(Action::REDRAW)
     }
+
+    fn update_subtitles<F: FnOnce(&mut ImageProgramData)>(&mut self, f: F) -> Action {
+        let (lock, _) = &*self.arc;
+        let mut data = lock.lock().unwrap();
+        // what a redraw depends on
+        let state =
+            |d: &ImageProgramData| (d.active.clone(), d.style.clone(), d.subtitles.is_some());
+        let before = state(&data);
+        f(&mut data);
+        data.active = match (&data.subtitles, data.position) {
+            (Some(subtitles), Some(position)) => subtitles.active(position),
+            _ => vec![],
+        };
+        if before == state(&data) {
+            return Action::empty();
+        }
+        self.need_redraw.store(true, Ordering::Relaxed);
+        Action::REDRAW
+    }
+
+    pub fn set_subtitles(&mut self, subtitles: Option<subtitle::Subtitles>) -> Action {
+        self.update_subtitles(|data| {
+            if subtitles.is_some() && data.renderer.is_none() {
+                match subtitle::Renderer::new() {
+                    Ok(renderer) => data.renderer = Some(renderer),
+                    Err(e) => error!("{e}"),
+                }
+            }
+            data.subtitles = subtitles;
+        })
+    }
+
+    pub fn set_subtitle_style(&mut self, style: subtitle::Style) -> Action {
+        self.update_subtitles(|data| data.style = style)
+    }
+
+    pub fn set_position(&mut self, position: Option<Duration>) -> Action {
+        self.update_subtitles(|data| data.position = position)
+    }
+
+    fn update_display<F: FnOnce(&mut ImageProgramData)>(&mut self, f: F) -> Action {
+        let (lock, _) = &*self.arc;
+        let mut data = lock.lock().unwrap();
+        let before = (data.mode, data.quality, data.zoom, data.pan);
+        f(&mut data);
+        if before == (data.mode, data.quality, data.zoom, data.pan) {
+            return Action::empty();
+        }
+        self.need_redraw.store(true, Ordering::Relaxed);
+        Action::REDRAW
+    }
+
+    pub fn set_display_mode(&mut self, mode: DisplayMode) -> Action {
+        self.update_display(|data| data.mode = mode)
+    }
+
+    pub fn set_filter_quality(&mut self, quality: FilterQuality) -> Action {
+        self.update_display(|data| data.quality = quality)
+    }
+
+    /// Zoom by `factor`, keeping the canvas `point` over the same place of the frame.
+    pub fn zoom_at(&mut self, point: (f32, f32), factor: f32) -> Action {
+        self.update_display(|data| {
+            let zoom = (data.zoom * factor).clamp(*ZOOM_RANGE.start(), *ZOOM_RANGE.end());
+            let k = zoom / data.zoom;
+            data.pan.0 = point.0 - (point.0 - data.pan.0) * k;
+            data.pan.1 = point.1 - (point.1 - data.pan.1) * k;
+            data.zoom = zoom;
+        })
+    }
+
+    pub fn pan_by(&mut self, dx: f32, dy: f32) -> Action {
+        self.update_display(|data| data.pan = (data.pan.0 + dx, data.pan.1 + dy))
+    }
+
+    pub fn reset_view(&mut self) -> Action {
+        self.update_display(|data| {
+            data.zoom = 1.0;
+            data.pan = (0.0, 0.0);
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -76,14 +223,29 @@ impl ImageProgramDrawer {
             guard = cvar.wait(guard).unwrap();
             //error!("[{}] wake", self.name);
         }
-        if let Some(pixmap) = guard.pixmap.take() {
+        let data = &*guard;
+        // the frame is kept to redraw the subtitles over it while paused
+        if let Some(ref pixmap) = data.pixmap {
             let paint = tiny_skia::PixmapPaint {
                 opacity: 1.0f32,
                 blend_mode: tiny_skia::BlendMode::Source,
-                quality: tiny_skia::FilterQuality::Nearest,
+                quality: data.quality,
             };
-            let tr = tiny_skia::Transform::identity();
+            let (width, height) = (target.width(), target.height());
+            let frame = (pixmap.width() as f32, pixmap.height() as f32);
+            let tr = data
+                .mode
+                .place(frame, (width as f32, height as f32))
+                .post_scale(data.zoom, data.zoom)
+                .post_translate(data.pan.0, data.pan.1);
+            // letterbox
+            target.fill(tiny_skia::Color::BLACK);
             target.draw_pixmap(0, 0, pixmap.as_ref(), &paint, tr, None);
+            // over the whole canvas, which keeps them readable in small or cropped frames
+            if let (Some(subtitles), Some(renderer)) = (&data.subtitles, &data.renderer) {
+                let cues: Vec<_> = data.active.iter().map(|&i| &subtitles.cues()[i]).collect();
+                renderer.draw(target, width, height, &cues, &data.style);
+            }
         }
         //error!("[{}] < drawer draw", self.name);
     }
@@ -138,6 +300,10 @@ impl_scope! {
         core: widget_core!(),
         _name: String,
         image_setter: ImageProgramSetter,
+        // of the frames, which the canvas is laid out for
+        size: (u32, u32),
+        // last known, from the hit tests kas makes on each motion, and from the presses
+        cursor: Option<Coord>,
         #[widget] canvas: Canvas<ImageProgram>,
     }
 
@@ -145,17 +311,161 @@ impl_scope! {
         pub fn new(name: &str, width: u32, height: u32) -> Self {
             let (pg, setter) = ImageProgram::new_and_take_setter(name);
             let size = kas::layout::LogicalSize::try_conv((width, height)).unwrap();
-            let canvas = Canvas::new(pg).with_size(size);
+            // follows the window, the frame size being only the ideal one
+            let canvas = Canvas::new(pg).with_scaling(|scaling| {
+                scaling.size = size;
+                scaling.min_factor = 0.25;
+                scaling.fix_aspect = false;
+                scaling.stretch = kas::layout::Stretch::High;
+            });
             Self {
                 core: Default::default(),
                 _name: name.to_string(),
                 image_setter: setter,
+                size: (width, height),
+                cursor: None,
                 canvas,
             }
         }
 
+        /// Show a frame, laying the canvas out again when its size changed.
         pub fn set_image(&mut self, rgba: Vec<u8>, width: u32, height: u32) -> Option<Action> {
-            return self.image_setter.set_image(rgba, width, height);
+            let mut action = self.image_setter.set_image(rgba, width, height)?;
+            if (width, height) != self.size {
+                self.size = (width, height);
+                let size = kas::layout::LogicalSize::try_conv((width, height)).unwrap();
+                action |= self.canvas.set_scaling(|scaling| scaling.size = size);
+            }
+            Some(action)
+        }
+
+        /// Draw `subtitles` over the frames, or stop drawing them.
+        pub fn set_subtitles(&mut self, subtitles: Option<subtitle::Subtitles>) -> Action {
+            self.image_setter.set_subtitles(subtitles)
+        }
+
+        pub fn set_subtitle_style(&mut self, style: subtitle::Style) -> Action {
+            self.image_setter.set_subtitle_style(style)
+        }
+
+        /// Show the cues at the playback `position`.
+        pub fn set_position(&mut self, position: Option<Duration>) -> Action {
+            self.image_setter.set_position(position)
+        }
+
+        pub fn set_display_mode(&mut self, mode: DisplayMode) -> Action {
+            self.image_setter.set_display_mode(mode)
+        }
+
+        /// Set how the frames are filtered when they are scaled.
+        pub fn set_filter_quality(&mut self, quality: FilterQuality) -> Action {
+            self.image_setter.set_filter_quality(quality)
+        }
+
+        /// Undo the zoom and the pan.
+        pub fn reset_view(&mut self) -> Action {
+            self.image_setter.reset_view()
+        }
+    }
+
+    impl Layout for Self {
+        fn find_id(&mut self, coord: Coord) -> Option<WidgetId> {
+            if !self.rect().contains(coord) {
+                return None;
+            }
+            // no motion events come without a grab, but the hovered widget is looked up on each
+            self.cursor = Some(coord);
+            Some(self.id())
+        }
+    }
+
+    impl Widget for Self {
+        fn handle_event(&mut self, mgr: &mut EventMgr, event: Event) -> Response {
+            match event {
+                Event::Scroll(delta) => {
+                    let steps = match delta {
+                        ScrollDelta::LineDelta(_, y) => y,
+                        ScrollDelta::PixelDelta(offset) => offset.1 as f32 / PIXELS_PER_STEP,
+                    };
+                    let rect = self.canvas.rect();
+                    // about the centre when the cursor wasn't seen over the canvas yet
+                    let at = match self.cursor.filter(|c| rect.contains(*c)) {
+                        Some(cursor) => cursor - rect.pos,
+                        None => Offset(rect.size.0 / 2, rect.size.1 / 2),
+                    };
+                    *mgr |= self.image_setter.zoom_at((at.0 as f32, at.1 as f32), ZOOM_STEP.powf(steps));
+                    Response::Used
+                }
+                Event::PressStart { press } if press.is_primary() => {
+                    self.cursor = Some(press.coord);
+                    if press.repetitions() == 2 {
+                        *mgr |= self.image_setter.reset_view();
+                    }
+                    press.grab(self.id()).with_mgr(mgr)
+                }
+                Event::PressMove { press, delta } => {
+                    self.cursor = Some(press.coord);
+                    *mgr |= self.image_setter.pan_by(delta.0 as f32, delta.1 as f32);
+                    Response::Used
+                }
+                Event::PressEnd { press, .. } => {
+                    self.cursor = Some(press.coord);
+                    Response::Used
+                }
+                Event::LostMouseHover => {
+                    self.cursor = None;
+                    Response::Used
+                }
+                _ => Response::Unused,
+            }
+        }
+    }
+}
+
+/// Frame shown for media without video nor cover art: a note on a dark background.
+pub fn placeholder(width: u32, height: u32) -> Vec<u8> {
+    let mut pixmap = Pixmap::new(width, height).expect("Pixmap::new");
+    pixmap.fill(tiny_skia::Color::from_rgba8(32, 32, 32, 255));
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let s = width.min(height) as f32 / 3.0;
+    let mut paint = tiny_skia::Paint::default();
+    paint.set_color_rgba8(160, 160, 160, 255);
+    let tr = tiny_skia::Transform::identity();
+    let head = tiny_skia::PathBuilder::from_circle(cx - s * 0.15, cy + s * 0.3, s * 0.18);
+    let stem = tiny_skia::Rect::from_xywh(cx - s * 0.01, cy - s * 0.5, s * 0.06, s * 0.8);
+    let flag = tiny_skia::Rect::from_xywh(cx - s * 0.01, cy - s * 0.5, s * 0.3, s * 0.08);
+    if let Some(head) = head {
+        pixmap.fill_path(&head, &paint, tiny_skia::FillRule::Winding, tr, None);
+    }
+    for rect in [stem, flag].into_iter().flatten() {
+        pixmap.fill_rect(rect, &paint, tr, None);
+    }
+    pixmap.take()
+}
+
+/// Decode a picture such as a cover art, shrunk to fit within `width`×`height`.
+pub fn decode_picture(bytes: &[u8], width: u32, height: u32) -> Option<(Vec<u8>, u32, u32)> {
+    let picture = match ::image::load_from_memory(bytes) {
+        Ok(picture) => picture,
+        Err(e) => {
+            error!("failed to decode the picture: {e}");
+            return None;
+        }
+    };
+    let picture = if picture.width() > width || picture.height() > height {
+        picture.thumbnail(width, height)
+    } else {
+        picture
+    };
+    let rgba = picture.to_rgba8();
+    let (w, h) = rgba.dimensions();
+    let mut data = rgba.into_raw();
+    // the pixmap takes premultiplied alpha
+    for px in data.chunks_exact_mut(4) {
+        let a = px[3] as u16;
+        for c in &mut px[..3] {
+            *c = (*c as u16 * a / 255) as u8;
         }
     }
+    Some((data, w, h))
 }
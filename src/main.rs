@@ -1,24 +1,104 @@
+mod bindings;
+mod folder;
+mod hideable;
 mod image;
 mod menu;
+mod offsets;
+mod playlist;
+mod playlist_file;
+mod settings;
+mod shortcut;
+mod subtitle;
+mod timeline;
+mod transport;
 mod video;
+use std::path::PathBuf;
 use std::sync::mpsc;
 use std::time::Duration;
 
+use kas::event::ModifiersState;
 use kas::prelude::*;
+use kas::widgets::dialog::MessageBox;
+use kas::widgets::Row;
 use log::error;
 use log::info;
 
+use bindings::PlayerCommand;
+use hideable::Hideable;
 use menu::Menu;
+use settings::Settings;
+use timeline::Timeline;
+use transport::Transport;
+
+/// Size of the canvas until the first frame, and of the pictures shown without video.
+const WIDTH: u32 = 720;
+const HEIGHT: u32 = 480;
+
+/// Playback rates stepped through by the slower and faster commands.
+const RATES: &[f64] = &[0.25, 0.5, 1.0, 1.5, 2.0, 4.0];
+
+/// Seconds skipped by the seek commands, and by their long variants.
+const SHORT_SKIP: f64 = 5.0;
+const LONG_SKIP: f64 = 60.0;
 
 #[derive(Clone, Debug)]
 pub enum GlobalMsg {
     TryLoadMovie,
+    TryOpenFolder,
+    TryAddToPlaylist,
+    TryOpenPlaylist,
+    TrySavePlaylist,
+    PlaylistNext,
+    PlaylistPrevious,
+    PlayIndex(usize),
+    SetShuffle(bool),
+    SetRepeatAll(bool),
+    Pause,
+    Resume,
+    Stop,
+    Seek(video::Position),
+    Scrub(video::Position),
+    StepForward,
+    StepBackward,
+    SetRate(f64),
+    SetScaletempo(bool),
+    SetVisualization(Option<String>),
+    SetDisplayMode(image::DisplayMode),
+    SetFilterQuality(image::FilterQuality),
+    SetTheme(String),
+    ChangeFontSize(f32),
+    ResetView,
+    SeekBy(f64),
+    StepRate(i32),
+    ToggleFullscreen,
+    ChangeVolume(f64),
+    SetMute(bool),
+    ToggleMute,
+    SelectAudioTrack(usize),
+    TryLoadSubtitle,
+    RemoveSubtitle,
+    ScaleSubtitle(f32),
+    SetSubtitleOutline(bool),
+    SetSubtitleTop(bool),
+    SelectTextTrack(Option<usize>),
+    NudgeAudioDelay(i64),
+    NudgeSubtitleDelay(i64),
+    SetLoop(bool),
+    MarkIn,
+    MarkOut,
+    ClearMarks,
+    ShowShortcuts,
 }
 
 #[derive(Debug)]
 enum Msg {
     LoadMovieNone,
+    OpenMovie(url::Url),
+    AddToPlaylist(Vec<url::Url>),
+    OpenPlaylist(Vec<url::Url>),
+    LoadSubtitle(subtitle::Subtitles),
     LoadMovie(url::Url),
+    ReloadMovie,
     LoadMovieFail,
     LoadMovieSuccess,
 }
@@ -27,78 +107,671 @@ impl_scope! {
     #[widget{
         layout = column: [
             self.menu,
-            self.image,
+            row: [self.image, self.playlist_panel],
+            self.timeline,
+            self.transport,
+            self.offset_bar,
         ];
     }]
     #[derive(Debug)]
     struct Main {
         core: widget_core!(),
-        #[widget] menu: Menu,
+        // first, to have its keys before the ones of the menus
+        #[widget] shortcuts: Row<shortcut::Shortcut>,
+        #[widget] menu: Hideable<Menu>,
         #[widget] image: image::Image,
+        #[widget] playlist_panel: Hideable<playlist::Panel>,
+        #[widget] timeline: Hideable<Timeline>,
+        #[widget] transport: Hideable<Transport>,
+        #[widget] offset_bar: Hideable<offsets::OffsetBar>,
+        bindings: bindings::Bindings,
+        settings: Settings,
+        // of the contents, saved when the window closes
+        window_size: Option<(u32, u32)>,
+        fullscreen: bool,
         n_images: usize,
+        playlist: playlist::Playlist,
         streamer: Option<video::Streamer>,
+        streamer_config: video::Config,
+        volume: f64,
+        muted: bool,
+        subtitle_style: subtitle::Style,
+        // encoded cover art shown for audio-only media
+        cover: Option<Vec<u8>>,
+        offsets: offsets::Offsets,
+        offset_store: offsets::Store,
+        resume_at: Option<(Duration, f64)>,
+        loop_all: bool,
+        mark_in: Option<Duration>,
+        ab: Option<(Duration, Duration)>,
         msg_receiver: Option<mpsc::Receiver<video::VideoMessage>>,
         video_watcher_interval: Duration,
     }
 
     impl Self {
-        fn new() -> Self {
-            let mut img = image::Image::new("movie", 720, 480);
+        fn new(settings: Settings) -> Self {
+            let mut img = image::Image::new("movie", WIDTH, HEIGHT);
             let mut data = Vec::new();
-            data.resize(720 * 480 * 4, 0);
-            img.set_image(data, 720, 480);
+            data.resize((WIDTH * HEIGHT * 4) as usize, 0);
+            img.set_image(data, WIDTH, HEIGHT);
+            let _ = img.set_display_mode(settings.display_mode);
+            let streamer_config = video::Config::default();
+            let bindings = bindings::Bindings::load();
             Self {
                 core: Default::default(),
-                menu: Menu::new(),
+                shortcuts: shortcut::accelerators(&bindings),
+                menu: Hideable::new(Menu::new(&streamer_config, &settings)),
                 image: img,
+                playlist_panel: Hideable::new(playlist::Panel::new()),
+                timeline: Hideable::new(Timeline::new()),
+                transport: Hideable::new(Transport::new()),
+                offset_bar: Hideable::new(offsets::OffsetBar::new()),
+                bindings,
+                fullscreen: false,
                 n_images: 0,
+                playlist: playlist::Playlist::new(),
                 streamer: None,
+                streamer_config,
+                volume: settings.volume,
+                muted: settings.muted,
+                subtitle_style: subtitle::Style::default(),
+                cover: None,
+                offsets: Default::default(),
+                offset_store: offsets::Store::load(),
+                resume_at: None,
+                loop_all: false,
+                mark_in: None,
+                ab: None,
                 msg_receiver: None,
                 video_watcher_interval: Duration::from_secs(1),
+                window_size: settings.window_size,
+                settings,
+            }
+        }
+
+        /// Open the next file dialogs where `url` is.
+        fn remember_directory(&mut self, url: &url::Url) {
+            if let Some(dir) = settings::directory_of(url) {
+                self.settings.update(|s| s.last_directory = Some(dir));
+            }
+        }
+
+        fn control_streamer<F>(&mut self, mgr: &mut EventMgr, f: F)
+        where
+            F: FnOnce(&mut video::Streamer) -> Result<(), video::Error>,
+        {
+            if let Some(ref mut streamer) = self.streamer {
+                if let Err(e) = f(streamer) {
+                    error!("{:?}", e);
+                }
+            }
+            self.update_transport(mgr);
+        }
+
+        fn repeat(&self) -> video::Repeat {
+            match self.ab {
+                Some((a, b)) => video::Repeat::Range(a, b),
+                None if self.loop_all => video::Repeat::All,
+                None => video::Repeat::Off,
             }
         }
+
+        fn apply_repeat(&mut self, mgr: &mut EventMgr) {
+            let repeat = self.repeat();
+            self.control_streamer(mgr, |s| s.set_repeat(repeat));
+            *mgr |= self.transport.set_repeat(repeat, self.mark_in);
+        }
+
+        fn play_current(&mut self, mgr: &mut EventMgr) {
+            if let Some(url) = self.playlist.current() {
+                mgr.push(Msg::LoadMovie(url.clone()));
+            }
+            self.update_playlist(mgr);
+        }
+
+        fn update_playlist(&mut self, mgr: &mut EventMgr) {
+            *mgr |= self.playlist_panel.set_playlist(&self.playlist);
+            if let Some(ref mut streamer) = self.streamer {
+                streamer.set_next_uri(self.playlist.peek_next().cloned());
+            }
+        }
+
+        fn update_tracks(&mut self, mgr: &mut EventMgr) {
+            let labels = |tracks: Vec<video::Track>| tracks.iter().map(|t| t.to_string()).collect();
+            let (audio, current_audio, text, current_text) = match self.streamer {
+                Some(ref streamer) => (
+                    labels(streamer.audio_tracks()),
+                    streamer.current_audio_track(),
+                    labels(streamer.text_tracks()),
+                    streamer.current_text_track(),
+                ),
+                None => (vec![], None, vec![], None),
+            };
+            *mgr |= self.menu.set_audio_tracks(audio, current_audio);
+            *mgr |= self.menu.set_text_tracks(text, current_text);
+        }
+
+        /// Show the cover art of audio-only media, or a placeholder without one.
+        fn update_cover(&mut self, mgr: &mut EventMgr, force: bool) {
+            let cover = match self.streamer {
+                // no frames come, neither from a video nor from a visualizer
+                Some(ref streamer) if streamer.size() == (0, 0) => streamer.cover_art(),
+                _ => return,
+            };
+            if !force && cover == self.cover {
+                return;
+            }
+            let (data, width, height) = cover
+                .as_deref()
+                .and_then(|bytes| image::decode_picture(bytes, WIDTH, HEIGHT))
+                .unwrap_or_else(|| (image::placeholder(WIDTH, HEIGHT), WIDTH, HEIGHT));
+            self.cover = cover;
+            if let Some(a) = self.image.set_image(data, width, height) {
+                *mgr |= a;
+            }
+        }
+
+        fn load_offsets(&mut self, mgr: &mut EventMgr, url: &url::Url) {
+            self.offsets = self.offset_store.get(url);
+            self.apply_offsets(mgr);
+        }
+
+        fn apply_offsets(&mut self, mgr: &mut EventMgr) {
+            let offsets = self.offsets;
+            if let Some(ref mut streamer) = self.streamer {
+                streamer.set_audio_delay(offsets.audio);
+                streamer.set_subtitle_delay(offsets.subtitle);
+            }
+            *mgr |= self.offset_bar.set_offsets(offsets);
+        }
+
+        fn nudge_offsets<F: FnOnce(&mut offsets::Offsets)>(&mut self, mgr: &mut EventMgr, f: F) {
+            let Some(uri) = self.streamer.as_ref().map(|s| s.uri()) else {
+                return;
+            };
+            f(&mut self.offsets);
+            info!("offsets of {}: {:?}", uri, self.offsets);
+            self.apply_offsets(mgr);
+            self.offset_store.set(&uri, self.offsets);
+        }
+
+        /// Whether the media can be seeked in, rate changes and backward steps being seeks too.
+        fn seekable(&self) -> bool {
+            self.streamer.as_ref().is_some_and(|s| s.seekable())
+        }
+
+        fn update_seekable(&mut self, mgr: &mut EventMgr) {
+            let (seekable, live) = match self.streamer {
+                Some(ref streamer) => (streamer.seekable(), streamer.is_live()),
+                None => (true, false),
+            };
+            self.timeline.set_seekable(mgr, seekable, live);
+            self.transport.set_seekable(mgr, seekable);
+        }
+
+        // polls five times a frame, variable frame rates being measured while playing
+        fn update_watcher_interval(&mut self) {
+            if let Some(ref streamer) = self.streamer {
+                let interval = streamer.frame_duration() / 5;
+                self.video_watcher_interval = interval.max(Duration::from_millis(1));
+            }
+        }
+
+        /// Give the whole window to the video, hiding the menus and the controls.
+        ///
+        /// The window itself keeps its size and decorations: kas 0.13 gives no access to it,
+        /// `EventState::winit_window` being compiled out.
+        fn set_fullscreen(&mut self, mgr: &mut EventMgr, fullscreen: bool) {
+            self.fullscreen = fullscreen;
+            self.menu.set_hidden(mgr, fullscreen);
+            self.playlist_panel.set_hidden(mgr, fullscreen);
+            self.timeline.set_hidden(mgr, fullscreen);
+            self.transport.set_hidden(mgr, fullscreen);
+            self.offset_bar.set_hidden(mgr, fullscreen);
+        }
+
+        fn seek_by(&mut self, mgr: &mut EventMgr, seconds: f64) {
+            if !self.seekable() {
+                return;
+            }
+            let Some(ref streamer) = self.streamer else {
+                return;
+            };
+            let Some(position) = streamer.position() else {
+                return;
+            };
+            let mut target = (position.as_secs_f64() + seconds).max(0.0);
+            if let Some(duration) = streamer.duration() {
+                target = target.min(duration.as_secs_f64());
+            }
+            let target = Duration::from_secs_f64(target);
+            self.control_streamer(mgr, |s| s.seek(video::Position::Time(target), false));
+        }
+
+        /// Go to the next or previous rate of [`RATES`], keeping the direction of playback.
+        fn step_rate(&mut self, mgr: &mut EventMgr, step: i32) {
+            if !self.seekable() {
+                return;
+            }
+            let Some(ref streamer) = self.streamer else {
+                return;
+            };
+            let rate = streamer.rate();
+            // the rates of the menu are the ones of the list
+            let index = RATES.iter().position(|r| *r >= rate.abs()).unwrap_or(RATES.len() - 1);
+            let index = (index as i32 + step).clamp(0, RATES.len() as i32 - 1) as usize;
+            let new_rate = RATES[index].copysign(rate);
+            if new_rate != rate {
+                self.control_streamer(mgr, |s| s.set_rate(new_rate));
+            }
+        }
+
+        fn run_command(&mut self, mgr: &mut EventMgr, command: PlayerCommand) -> Response {
+            let msg = match command {
+                PlayerCommand::PlayPause => match self.streamer.as_ref().map(|s| s.state()) {
+                    Some(video::PlaybackState::Playing) => GlobalMsg::Pause,
+                    _ => GlobalMsg::Resume,
+                },
+                PlayerCommand::Stop => GlobalMsg::Stop,
+                PlayerCommand::SeekBackward => GlobalMsg::SeekBy(-SHORT_SKIP),
+                PlayerCommand::SeekForward => GlobalMsg::SeekBy(SHORT_SKIP),
+                PlayerCommand::SeekBackwardLong => GlobalMsg::SeekBy(-LONG_SKIP),
+                PlayerCommand::SeekForwardLong => GlobalMsg::SeekBy(LONG_SKIP),
+                PlayerCommand::StepBackward => GlobalMsg::StepBackward,
+                PlayerCommand::StepForward => GlobalMsg::StepForward,
+                PlayerCommand::Slower => GlobalMsg::StepRate(-1),
+                PlayerCommand::Faster => GlobalMsg::StepRate(1),
+                PlayerCommand::AudioEarlier => GlobalMsg::NudgeAudioDelay(-offsets::STEP),
+                PlayerCommand::AudioLater => GlobalMsg::NudgeAudioDelay(offsets::STEP),
+                PlayerCommand::SubtitleEarlier => GlobalMsg::NudgeSubtitleDelay(-offsets::STEP),
+                PlayerCommand::SubtitleLater => GlobalMsg::NudgeSubtitleDelay(offsets::STEP),
+                PlayerCommand::MarkIn => GlobalMsg::MarkIn,
+                PlayerCommand::MarkOut => GlobalMsg::MarkOut,
+                PlayerCommand::VolumeDown => GlobalMsg::ChangeVolume(-0.1),
+                PlayerCommand::VolumeUp => GlobalMsg::ChangeVolume(0.1),
+                PlayerCommand::ToggleMute => GlobalMsg::ToggleMute,
+                PlayerCommand::ToggleFullscreen => GlobalMsg::ToggleFullscreen,
+                // escape is left to the other widgets out of fullscreen
+                PlayerCommand::ExitFullscreen if !self.fullscreen => return Response::Unused,
+                PlayerCommand::ExitFullscreen => GlobalMsg::ToggleFullscreen,
+                PlayerCommand::PlaylistPrevious => GlobalMsg::PlaylistPrevious,
+                PlayerCommand::PlaylistNext => GlobalMsg::PlaylistNext,
+                PlayerCommand::ShowShortcuts => GlobalMsg::ShowShortcuts,
+            };
+            mgr.push(msg);
+            Response::Used
+        }
+
+        fn show_shortcuts(&self, mgr: &mut EventMgr) {
+            let mut text = String::new();
+            for (chord, command) in self.bindings.list() {
+                text += &format!("{chord}: {}\n", command.description());
+            }
+            let dialog = MessageBox::new("Shortcuts", text.trim_end().to_string());
+            mgr.add_window(Box::new(dialog));
+        }
+
+        fn update_transport(&mut self, mgr: &mut EventMgr) {
+            let state = self.streamer.as_ref().map(|s| s.state());
+            *mgr |= self.transport.set_state(state);
+            let rate = self.streamer.as_ref().map_or(1.0, |s| s.rate());
+            *mgr |= self.transport.set_rate(rate);
+        }
+    }
+
+    impl Layout for Self {
+        fn size_rules(&mut self, size_mgr: SizeMgr, axis: AxisInfo) -> SizeRules {
+            let rules = <Self as kas::layout::AutoLayout>::size_rules(self, size_mgr, axis);
+            // kas opens the window at its ideal size, having no window-level one, so until the window
+            // is laid out the contents ask for the size they were left at; the decorations and
+            // margins kas puts around them are the same as then
+            if self.core.rect.size != Size::ZERO {
+                return rules;
+            }
+            let Some((width, height)) = self.window_size else {
+                return rules;
+            };
+            let size = if axis.is_horizontal() { width } else { height } as i32;
+            let ideal = size.max(rules.min_size());
+            SizeRules::new(rules.min_size(), ideal, rules.margins(), rules.stretch())
+        }
+
+        fn set_rect(&mut self, mgr: &mut ConfigMgr, rect: Rect) {
+            <Self as kas::layout::AutoLayout>::set_rect(self, mgr, rect);
+            self.window_size = Some((rect.size.0 as u32, rect.size.1 as u32));
+        }
     }
 
     impl Widget for Self {
+        fn configure(&mut self, mgr: &mut ConfigMgr) {
+            // single-key shortcuts of the player and the transport bar, the menus needing Alt
+            mgr.enable_alt_bypass(self.id_ref(), true);
+            // bound keys which are commands, when no control takes them
+            mgr.register_nav_fallback(self.id());
+        }
+
         fn handle_message(&mut self, mgr: &mut EventMgr) {
             //error!(">handle_message");
             if let Some(msg) = mgr.try_pop::<GlobalMsg>() {
                 match msg {
                    GlobalMsg::TryLoadMovie => {
                         mgr.set_disabled(self.id(), true);
-                        mgr.push_spawn(self.id(), try_load_movie());
+                        mgr.push_spawn(self.id(), try_load_movie(self.settings.dialog_directory()));
+                    }
+                    GlobalMsg::TryOpenFolder => {
+                        mgr.set_disabled(self.id(), true);
+                        mgr.push_spawn(self.id(), try_open_folder(self.settings.dialog_directory()));
+                    }
+                    GlobalMsg::TryAddToPlaylist => {
+                        mgr.set_disabled(self.id(), true);
+                        mgr.push_spawn(self.id(), try_add_to_playlist(self.settings.dialog_directory()));
+                    }
+                    GlobalMsg::TryOpenPlaylist => {
+                        mgr.set_disabled(self.id(), true);
+                        mgr.push_spawn(self.id(), try_open_playlist(self.settings.dialog_directory()));
+                    }
+                    GlobalMsg::TrySavePlaylist => {
+                        mgr.set_disabled(self.id(), true);
+                        mgr.push_spawn(self.id(), try_save_playlist(self.settings.dialog_directory(), self.playlist.items().to_vec()));
+                    }
+                    GlobalMsg::PlaylistNext => {
+                        if self.playlist.next_item().is_some() {
+                            self.play_current(mgr);
+                        }
+                    }
+                    GlobalMsg::PlaylistPrevious => {
+                        if self.playlist.previous_item().is_some() {
+                            self.play_current(mgr);
+                        }
+                    }
+                    GlobalMsg::PlayIndex(index) => {
+                        if self.playlist.select(index).is_some() {
+                            self.play_current(mgr);
+                        }
+                    }
+                    GlobalMsg::SetShuffle(state) => {
+                        self.playlist.set_shuffle(state);
+                        self.update_playlist(mgr);
+                    }
+                    GlobalMsg::SetRepeatAll(state) => {
+                        self.playlist.set_repeat_all(state);
+                        self.update_playlist(mgr);
+                    }
+                    GlobalMsg::Pause => {
+                        self.control_streamer(mgr, |s| s.pause());
+                    }
+                    GlobalMsg::Resume => {
+                        self.control_streamer(mgr, |s| s.resume());
+                    }
+                    GlobalMsg::Stop => {
+                        self.control_streamer(mgr, |s| s.stop());
+                    }
+                    GlobalMsg::Seek(pos) => {
+                        self.control_streamer(mgr, |s| s.seek(pos, true));
+                    }
+                    GlobalMsg::Scrub(pos) => {
+                        self.control_streamer(mgr, |s| s.seek(pos, false));
+                    }
+                    GlobalMsg::StepForward => {
+                        self.control_streamer(mgr, |s| s.step_forward());
+                    }
+                    // the keys and the menus of these stay enabled, unlike the buttons
+                    GlobalMsg::StepBackward if self.seekable() => {
+                        self.control_streamer(mgr, |s| s.step_backward());
+                    }
+                    GlobalMsg::SetRate(rate) if self.seekable() => {
+                        self.control_streamer(mgr, |s| s.set_rate(rate));
+                    }
+                    GlobalMsg::StepBackward | GlobalMsg::SetRate(_) => {
+                        info!("the media can't be seeked in");
+                    }
+                    GlobalMsg::SetScaletempo(state) => {
+                        self.streamer_config.scaletempo = state;
+                        // the audio filter is fixed once the pipeline is built
+                        mgr.push(Msg::ReloadMovie);
+                    }
+                    GlobalMsg::SetVisualization(name) => {
+                        self.streamer_config.visualization = name;
+                        // the visualizer is plugged when the pipeline is built
+                        if self.streamer.as_ref().is_some_and(|s| !s.has_video()) {
+                            mgr.push(Msg::ReloadMovie);
+                        }
+                    }
+                    GlobalMsg::SetDisplayMode(mode) => {
+                        *mgr |= self.image.set_display_mode(mode);
+                        self.settings.update(|s| s.display_mode = mode);
+                    }
+                    GlobalMsg::SetFilterQuality(quality) => {
+                        *mgr |= self.image.set_filter_quality(quality);
+                    }
+                    GlobalMsg::ResetView => {
+                        *mgr |= self.image.reset_view();
+                    }
+                    GlobalMsg::SetTheme(name) => {
+                        mgr.adjust_theme(|theme| theme.set_scheme(&name));
+                        self.settings.update(|s| s.theme = Some(name));
+                    }
+                    GlobalMsg::ChangeFontSize(delta) => {
+                        let range = settings::FONT_SIZE_RANGE;
+                        let size = (self.settings.font_size + delta).clamp(*range.start(), *range.end());
+                        mgr.adjust_theme(|theme| theme.set_font_size(size));
+                        self.settings.update(|s| s.font_size = size);
+                    }
+                    GlobalMsg::SeekBy(seconds) => {
+                        self.seek_by(mgr, seconds);
+                    }
+                    GlobalMsg::StepRate(step) => {
+                        self.step_rate(mgr, step);
+                    }
+                    GlobalMsg::ToggleFullscreen => {
+                        self.set_fullscreen(mgr, !self.fullscreen);
+                    }
+                    GlobalMsg::ChangeVolume(delta) => {
+                        self.volume = (self.volume + delta).clamp(0.0, 1.0);
+                        info!("volume {:.0}%", self.volume * 100.0);
+                        let volume = self.volume;
+                        self.settings.update(|s| s.volume = volume);
+                        self.control_streamer(mgr, |s| {
+                            s.set_volume(volume);
+                            Ok(())
+                        });
+                    }
+                    GlobalMsg::SetMute(state) => {
+                        self.muted = state;
+                        *mgr |= self.menu.set_muted(state);
+                        self.settings.update(|s| s.muted = state);
+                        self.control_streamer(mgr, |s| {
+                            s.set_mute(state);
+                            Ok(())
+                        });
+                    }
+                    GlobalMsg::ToggleMute => {
+                        mgr.push(GlobalMsg::SetMute(!self.muted));
+                    }
+                    GlobalMsg::SelectAudioTrack(index) => {
+                        self.control_streamer(mgr, |s| s.select_audio_track(index));
+                        self.update_tracks(mgr);
+                    }
+                    GlobalMsg::TryLoadSubtitle => {
+                        mgr.set_disabled(self.id(), true);
+                        mgr.push_spawn(self.id(), try_load_subtitle(self.settings.dialog_directory()));
+                    }
+                    GlobalMsg::RemoveSubtitle => {
+                        *mgr |= self.image.set_subtitles(None);
+                    }
+                    GlobalMsg::ScaleSubtitle(factor) => {
+                        self.subtitle_style.font_size *= factor;
+                        *mgr |= self.image.set_subtitle_style(self.subtitle_style.clone());
+                    }
+                    GlobalMsg::SetSubtitleOutline(state) => {
+                        self.subtitle_style.outline = if state { subtitle::Style::default().outline } else { 0.0 };
+                        *mgr |= self.image.set_subtitle_style(self.subtitle_style.clone());
+                    }
+                    GlobalMsg::SetSubtitleTop(state) => {
+                        self.subtitle_style.placement = if state {
+                            subtitle::Placement::Top
+                        } else {
+                            subtitle::Placement::Bottom
+                        };
+                        *mgr |= self.image.set_subtitle_style(self.subtitle_style.clone());
+                    }
+                    GlobalMsg::SelectTextTrack(index) => {
+                        self.control_streamer(mgr, |s| s.select_text_track(index));
+                        self.update_tracks(mgr);
+                    }
+                    GlobalMsg::NudgeAudioDelay(ms) => {
+                        self.nudge_offsets(mgr, |o| o.audio += ms);
+                    }
+                    GlobalMsg::NudgeSubtitleDelay(ms) => {
+                        self.nudge_offsets(mgr, |o| o.subtitle += ms);
+                    }
+                    GlobalMsg::SetLoop(state) => {
+                        self.loop_all = state;
+                        self.apply_repeat(mgr);
+                    }
+                    GlobalMsg::MarkIn | GlobalMsg::MarkOut if !self.seekable() => {
+                        info!("the media can't be seeked in");
+                    }
+                    GlobalMsg::MarkIn => {
+                        self.mark_in = self.streamer.as_ref().and_then(|s| s.position());
+                        if self.ab.take().is_some() {
+                            self.apply_repeat(mgr);
+                        } else {
+                            let repeat = self.repeat();
+                            *mgr |= self.transport.set_repeat(repeat, self.mark_in);
+                        }
+                    }
+                    GlobalMsg::MarkOut => {
+                        let out = self.streamer.as_ref().and_then(|s| s.position());
+                        match (self.mark_in, out) {
+                            (Some(a), Some(b)) if a < b => {
+                                self.mark_in = None;
+                                self.ab = Some((a, b));
+                                self.apply_repeat(mgr);
+                            }
+                            _ => info!("mark the in point before the out point"),
+                        }
+                    }
+                    GlobalMsg::ClearMarks => {
+                        self.mark_in = None;
+                        self.ab = None;
+                        self.apply_repeat(mgr);
+                    }
+                    GlobalMsg::ShowShortcuts => {
+                        self.show_shortcuts(mgr);
                     }
                 }
             }
+            if let Some(shortcut::KeyPress(key)) = mgr.try_pop() {
+                let chord = bindings::Chord { modifiers: mgr.modifiers(), key };
+                if let Some(command) = self.bindings.get(chord) {
+                    self.run_command(mgr, command);
+                } else if chord.modifiers == ModifiersState::ALT && !self.fullscreen {
+                    // the key of a menu, which the shortcuts took first
+                    self.menu.open(mgr, key);
+                }
+            }
             if let Some(msg) = mgr.try_pop::<Msg>() {
                 match msg {
                     Msg::LoadMovieNone => {
                         mgr.set_disabled(self.id(), false);
                     }
+                    Msg::OpenMovie(url) => {
+                        mgr.set_disabled(self.id(), false);
+                        self.remember_directory(&url);
+                        self.playlist.clear();
+                        self.playlist.extend([url]);
+                        self.playlist.select(0);
+                        self.play_current(mgr);
+                    }
+                    Msg::OpenPlaylist(urls) => {
+                        mgr.set_disabled(self.id(), false);
+                        if let Some(url) = urls.first() {
+                            self.remember_directory(url);
+                        }
+                        self.playlist.clear();
+                        self.playlist.extend(urls);
+                        if self.playlist.next_item().is_some() {
+                            self.play_current(mgr);
+                        } else {
+                            self.update_playlist(mgr);
+                        }
+                    }
+                    Msg::LoadSubtitle(subtitles) => {
+                        mgr.set_disabled(self.id(), false);
+                        *mgr |= self.image.set_subtitles(Some(subtitles));
+                        // not to show the embedded ones over the file
+                        self.control_streamer(mgr, |s| s.select_text_track(None));
+                        self.update_tracks(mgr);
+                    }
+                    Msg::AddToPlaylist(urls) => {
+                        mgr.set_disabled(self.id(), false);
+                        if let Some(url) = urls.first() {
+                            self.remember_directory(url);
+                        }
+                        let first = self.playlist.items().len();
+                        self.playlist.extend(urls);
+                        if self.streamer.is_none() && self.playlist.select(first).is_some() {
+                            self.play_current(mgr);
+                        } else {
+                            self.update_playlist(mgr);
+                        }
+                    }
                     Msg::LoadMovie(url) => {
                         mgr.set_disabled(self.id(), false);
                         error!("url is: {}", url);
                         //let (msg_sender, msg_receiver) = std::sync::mpsc::sync_channel(10);
                         error!("creating video...");
-                        match video::Streamer::new(&url, true) {
+                        if self.resume_at.is_none() {
+                            *mgr |= self.image.set_subtitles(None);
+                        }
+                        match video::Streamer::new(&url, &self.streamer_config) {
                             Err(e) => {
                                 error!("{:?}", e);
                                 mgr.push(Msg::LoadMovieFail);
                             }
                             Ok(mut vs) => {
                                 self.msg_receiver = vs.take_msg_receiver();
-                                let fps = vs.framerate();
                                 self.streamer = Some(vs);
                                 self.n_images = 0;
 
                                 //mgr.push_spawn(self.id(), video_message_handler("dummy".to_string(), msg_receiver.unwrap()));
-                                self.video_watcher_interval = Duration::from_secs_f64(1.0f64 / (fps * 5.0f64));
+                                self.update_watcher_interval();
                                 mgr.request_update(self.id(), 3939, self.video_watcher_interval, true);
-                                self.streamer.as_mut().unwrap().start();
+                                let streamer = self.streamer.as_mut().unwrap();
+                                streamer.set_volume(self.volume);
+                                streamer.set_mute(self.muted);
+                                streamer.start();
+                                self.load_offsets(mgr, &url);
+                                self.update_cover(mgr, true);
+                                self.update_seekable(mgr);
+                                self.update_tracks(mgr);
+                                if self.resume_at.is_none() {
+                                    self.mark_in = None;
+                                    self.ab = None;
+                                }
+                                self.apply_repeat(mgr);
+                                self.update_playlist(mgr);
+                                if let Some((position, rate)) = self.resume_at.take() {
+                                    self.control_streamer(mgr, |s| {
+                                        s.seek(video::Position::Time(position), true)?;
+                                        if rate != 1.0 {
+                                            s.set_rate(rate)?;
+                                        }
+                                        Ok(())
+                                    });
+                                }
+                                self.update_transport(mgr);
                                 mgr.push(Msg::LoadMovieSuccess);
                             }
                         }
                     }
+                    Msg::ReloadMovie => {
+                        if let Some(ref streamer) = self.streamer {
+                            self.resume_at = streamer.position().map(|p| (p, streamer.rate()));
+                            mgr.push(Msg::LoadMovie(streamer.uri()));
+                        }
+                    }
                     Msg::LoadMovieFail => {
                         error!("load movie failed");
                     }
@@ -112,26 +785,43 @@ impl_scope! {
         fn handle_event(&mut self, mgr: &mut EventMgr, ev: Event) -> Response {
             match ev {
                 Event::TimerUpdate(3939) => {
-                    let mut new_sample: Option<(Vec<u8>, u32, u32)> = None;
+                    let mut new_sample: Option<video::Frame> = None;
+                    let mut state_changed = false;
+                    let mut uri_changed = false;
+                    let mut duration_changed = false;
+                    let mut tags_changed = false;
                     if let Some(ref mut msg_receiver) = self.msg_receiver {
                         for msg in msg_receiver.try_iter() {
                             match msg {
-                                video::VideoMessage::NewSample(data, width, height) => {
+                                video::VideoMessage::NewSample(frame) => {
                                     self.n_images += 1;
-                                    new_sample = Some((data, width, height));
+                                    new_sample = Some(frame);
                                     //error!("[{}] new sample", self.n_images);
                                 }
+                                video::VideoMessage::UriChanged(url) => {
+                                    info!("[{}] continue with {}", self.n_images, url);
+                                    uri_changed = true;
+                                }
                                 video::VideoMessage::GstMessage(gst_msg) => {
                                     match gst_msg.view() {
                                         gstreamer::MessageView::Eos(..) => {
                                             error!("[{}] eos", self.n_images);
-                                            //return Msg::VideoFin;
-                                            return Response::Used;
+                                            // polled on, the media may be played or seeked in again
+                                            state_changed = true;
                                         }
                                         gstreamer::MessageView::Error(err) => {
                                             error!("[{}] Error: {} ({:?})", self.n_images, err.error(), err.debug());
-                                            //return Msg::VideoFin;
-                                            return Response::Used;
+                                            state_changed = true;
+                                        }
+                                        gstreamer::MessageView::StateChanged(..) => {
+                                            state_changed = true;
+                                        }
+                                        gstreamer::MessageView::Tag(..) => {
+                                            tags_changed = true;
+                                        }
+                                        gstreamer::MessageView::StreamStart(..)
+                                        | gstreamer::MessageView::DurationChanged(..) => {
+                                            duration_changed = true;
                                         }
                                         _ev => {
                                             //error!("unknown event: {:?}", ev);
@@ -141,14 +831,60 @@ impl_scope! {
                             }
                         }
                     }
-                    if let Some((data, width, height)) = new_sample {
-                        if let  Some(a) = self.image.set_image(data, width, height) {
+                    if uri_changed {
+                        self.playlist.next_item();
+                        self.update_playlist(mgr);
+                        // external subtitles belong to the previous item
+                        *mgr |= self.image.set_subtitles(None);
+                        if let Some(ref mut streamer) = self.streamer {
+                            streamer.update_video_info();
+                        }
+                        if let Some(uri) = self.streamer.as_ref().map(|s| s.uri()) {
+                            self.load_offsets(mgr, &uri);
+                        }
+                        self.update_cover(mgr, true);
+                    }
+                    if duration_changed {
+                        if let Some(ref mut streamer) = self.streamer {
+                            streamer.update_duration();
+                        }
+                        self.update_tracks(mgr);
+                        self.update_seekable(mgr);
+                    }
+                    if tags_changed || duration_changed {
+                        self.update_cover(mgr, false);
+                    }
+                    if let Some(ref streamer) = self.streamer {
+                        *mgr |= self.timeline.set_position(streamer.position(), streamer.duration());
+                        *mgr |= self.timeline.set_frame(streamer.current_frame());
+                        // the native subtitles follow the delay the same as playbin's
+                        let delay = Duration::from_millis(self.offsets.subtitle.unsigned_abs());
+                        let position = streamer.position().map(|p| match self.offsets.subtitle {
+                            ms if ms < 0 => p + delay,
+                            _ => p.saturating_sub(delay),
+                        });
+                        *mgr |= self.image.set_position(position);
+                    }
+                    if state_changed {
+                        self.update_transport(mgr);
+                    }
+                    if let Some(frame) = new_sample {
+                        if let  Some(a) = self.image.set_image(frame.data, frame.width, frame.height) {
                             *mgr |= a;
                         }
                     }
+                    self.update_watcher_interval();
                     let _ = mgr.request_update(self.id(), 3939, self.video_watcher_interval, true);
                     Response::Used
                 }
+                Event::Command(cmd) => {
+                    let modifiers = mgr.modifiers();
+                    let command = mgr.config().shortcuts(|s| self.bindings.get_command(modifiers, cmd, s));
+                    match command {
+                        Some(command) => self.run_command(mgr, command),
+                        None => Response::Unused,
+                    }
+                }
                 _ => {
                     Response::Unused
                 }
@@ -157,32 +893,131 @@ impl_scope! {
     }
     impl Window for Self {
         fn title(&self) -> &str { "my f2f" }
+
+        fn handle_closure(&mut self, _: &mut EventMgr) {
+            let window_size = self.window_size;
+            self.settings.update(|s| s.window_size = window_size);
+        }
     }
 }
 
-async fn try_load_movie() -> Msg {
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "avi", "mp3", "flac", "ogg", "oga", "opus", "wav", "m4a",
+];
+
+/// The `file://` url of `path`, escaping the characters of the name which are special in urls.
+fn file_url(path: &std::path::Path) -> url::Url {
+    url::Url::from_file_path(path).unwrap_or_else(|_| {
+        // relative paths have no url of their own
+        let path = path.to_string_lossy();
+        url::Url::parse(&format!("file://{}", path.replace(":", "/"))).expect("parse url")
+    })
+}
+
+async fn try_load_movie(dir: PathBuf) -> Msg {
     // todo mutex
     let file = rfd::AsyncFileDialog::new()
-        .add_filter("movie", &["mp4", "mkv", "avi"])
-        .set_directory("/")
+        .add_filter("media", MEDIA_EXTENSIONS)
+        .set_directory(dir)
         .pick_file()
         .await;
     match file {
         None => Msg::LoadMovieNone,
-        Some(f) => {
-            let path = f.path().to_string_lossy();
-            let url =
-                url::Url::parse(&format!("file://{}", path.replace(":", "/"))).expect("parse url");
-            Msg::LoadMovie(url)
+        Some(f) => Msg::OpenMovie(file_url(f.path())),
+    }
+}
+
+async fn try_open_folder(dir: PathBuf) -> Msg {
+    let folder = rfd::AsyncFileDialog::new()
+        .set_directory(dir)
+        .pick_folder()
+        .await;
+    let Some(folder) = folder else {
+        return Msg::LoadMovieNone;
+    };
+    let dir = folder.path().to_path_buf();
+    // typefinding reads every file, keep it off the runtime threads
+    match tokio::task::spawn_blocking(move || folder::scan(&dir)).await {
+        Ok(paths) => Msg::OpenPlaylist(paths.iter().map(|p| file_url(p)).collect()),
+        Err(e) => {
+            error!("{:?}", e);
+            Msg::LoadMovieNone
+        }
+    }
+}
+
+async fn try_load_subtitle(dir: PathBuf) -> Msg {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("subtitle", &["srt", "vtt"])
+        .set_directory(dir)
+        .pick_file()
+        .await;
+    let Some(f) = file else {
+        return Msg::LoadMovieNone;
+    };
+    match subtitle::load(f.path()) {
+        Ok(subtitles) => Msg::LoadSubtitle(subtitles),
+        Err(e) => {
+            error!("{}: {}", f.path().display(), e);
+            Msg::LoadMovieNone
+        }
+    }
+}
+
+async fn try_add_to_playlist(dir: PathBuf) -> Msg {
+    let files = rfd::AsyncFileDialog::new()
+        .add_filter("media", MEDIA_EXTENSIONS)
+        .set_directory(dir)
+        .pick_files()
+        .await;
+    match files {
+        None => Msg::LoadMovieNone,
+        Some(files) => Msg::AddToPlaylist(files.iter().map(|f| file_url(f.path())).collect()),
+    }
+}
+
+async fn try_open_playlist(dir: PathBuf) -> Msg {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("playlist", playlist_file::Format::EXTENSIONS)
+        .set_directory(dir)
+        .pick_file()
+        .await;
+    let Some(f) = file else {
+        return Msg::LoadMovieNone;
+    };
+    match playlist_file::load(f.path()) {
+        Ok(urls) => Msg::OpenPlaylist(urls),
+        Err(e) => {
+            error!("{}: {}", f.path().display(), e);
+            Msg::LoadMovieNone
+        }
+    }
+}
+
+async fn try_save_playlist(dir: PathBuf, urls: Vec<url::Url>) -> Msg {
+    let file = rfd::AsyncFileDialog::new()
+        .add_filter("playlist", playlist_file::Format::EXTENSIONS)
+        .set_directory(dir)
+        .set_file_name("playlist.m3u8")
+        .save_file()
+        .await;
+    if let Some(f) = file {
+        if let Err(e) = playlist_file::save(f.path(), &urls) {
+            error!("{}: {}", f.path().display(), e);
         }
     }
+    Msg::LoadMovieNone
 }
 
 #[tokio::main]
 async fn main() -> kas::shell::Result<()> {
     env_logger::init();
-    let theme = kas::theme::SimpleTheme::new().with_font_size(24.0);
+    let settings = Settings::load();
+    let mut theme = kas::theme::SimpleTheme::new().with_font_size(settings.font_size);
+    if let Some(ref name) = settings.theme {
+        theme = theme.with_colours(name);
+    }
     let shell = kas::shell::DefaultShell::new(theme)?;
-    let main = Main::new();
+    let main = Main::new(settings);
     shell.with(main)?.run();
 }
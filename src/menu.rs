@@ -1,10 +1,99 @@
+use kas::event::{Command, ModifiersState, VirtualKeyCode as VK};
+use kas::layout;
 use kas::prelude::*;
-use kas::widgets::menu::MenuBar;
+use kas::theme::{FrameStyle, TextClass};
+use kas::widgets::menu::{Menu as MenuItem, MenuBar, MenuToggle, SubItems};
+use kas::widgets::{AccelLabel, RadioBox, RadioGroup};
 
+use super::image::{DisplayMode, FilterQuality};
+use super::settings::{Settings, COLOUR_SCHEMES};
+use super::video;
 use super::GlobalMsg;
 
+/// Titles of the menus of the bar, in their order.
+const TITLES: [&str; 6] = [
+    "&File",
+    "&Playback",
+    "A&udio",
+    "Su&btitles",
+    "&View",
+    "&Help",
+];
+
 #[derive(Clone, Debug)]
-enum Msg {}
+enum Msg {
+    Loop(bool),
+    Scaletempo(bool),
+    Mute(bool),
+    AudioTrack(usize),
+    TextTrack(Option<usize>),
+    SubtitleOutline(bool),
+    SubtitleTop(bool),
+    Visualization(Option<String>),
+    DisplayMode(DisplayMode),
+    FilterQuality(FilterQuality),
+    Theme(String),
+}
+
+impl_scope! {
+  /// A menu entry selected among the others of its group
+  #[autoimpl(Debug)]
+  #[widget {
+    layout = row: [self.radiobox, self.label];
+  }]
+  pub struct MenuRadio {
+    core: widget_core!(),
+    #[widget] radiobox: RadioBox,
+    #[widget] label: AccelLabel,
+  }
+
+  impl Layout for Self {
+    fn find_id(&mut self, coord: Coord) -> Option<WidgetId> {
+      self.rect().contains(coord).then(|| self.radiobox.id())
+    }
+
+    fn draw(&mut self, mut draw: DrawMgr) {
+      let mut draw = draw.re_id(self.radiobox.id());
+      draw.frame(self.rect(), FrameStyle::MenuEntry, Default::default());
+      <Self as layout::AutoLayout>::draw(self, draw);
+    }
+  }
+
+  impl Widget for Self {
+    fn configure(&mut self, mgr: &mut ConfigMgr) {
+      mgr.add_accel_keys(self.radiobox.id_ref(), self.label.keys());
+    }
+  }
+
+  impl MenuItem for Self {
+    fn sub_items(&mut self) -> Option<SubItems<'_>> {
+      Some(SubItems {
+        label: Some(&mut self.label),
+        toggle: Some(&mut self.radiobox),
+        ..Default::default()
+      })
+    }
+  }
+
+  impl Self {
+    pub fn new_on<T: Into<AccelString>, F>(label: T, group: RadioGroup, f: F) -> Self
+    where
+      F: Fn(&mut EventMgr) + 'static,
+    {
+      MenuRadio {
+        core: Default::default(),
+        radiobox: RadioBox::new_on(group, f),
+        label: AccelLabel::new(label).with_class(TextClass::MenuLabel),
+      }
+    }
+
+    #[must_use]
+    pub fn with_state(mut self, state: bool) -> Self {
+      self.radiobox = self.radiobox.with_state(state);
+      self
+    }
+  }
+}
 
 impl_scope! {
   #[widget{
@@ -16,22 +105,295 @@ impl_scope! {
   pub struct Menu {
     core: widget_core!(),
     #[widget] display: MenuBar,
+    // toggled states, kept when the menus are rebuilt
+    loop_all: bool,
+    scaletempo: bool,
+    muted: bool,
+    audio_tracks: Vec<String>,
+    current_audio: Option<usize>,
+    text_tracks: Vec<String>,
+    current_text: Option<usize>,
+    subtitle_outline: bool,
+    subtitle_top: bool,
+    visualizers: Vec<video::Visualizer>,
+    visualization: Option<String>,
+    display_mode: DisplayMode,
+    filter_quality: FilterQuality,
+    theme: Option<String>,
   }
   impl Self {
-    pub fn new() -> Self {
-      Menu {
+    pub fn new(config: &video::Config, settings: &Settings) -> Self {
+      let mut menu = Menu {
         core: Default::default(),
-        display: MenuBar::<kas::dir::Right>::builder()
-          .menu("&File", |menu| {
-            menu.entry("New &Movie", GlobalMsg::TryLoadMovie);
-          })
-          .build()
+        display: MenuBar::new(vec![]),
+        loop_all: false,
+        scaletempo: config.scaletempo,
+        muted: settings.muted,
+        audio_tracks: vec![],
+        current_audio: None,
+        text_tracks: vec![],
+        current_text: None,
+        subtitle_outline: true,
+        subtitle_top: false,
+        visualizers: video::visualizers(),
+        visualization: config.visualization.clone(),
+        display_mode: settings.display_mode,
+        filter_quality: FilterQuality::Bilinear,
+        theme: settings.theme.clone(),
+      };
+      menu.display = menu.build();
+      menu
+    }
+
+    fn build(&self) -> MenuBar {
+      let [file, playback, audio, subtitles, view, help] = TITLES;
+      MenuBar::<kas::dir::Right>::builder()
+        .menu(file, |menu| {
+          menu
+            .entry("New &Movie", GlobalMsg::TryLoadMovie)
+            .entry("Open &Folder", GlobalMsg::TryOpenFolder)
+            .entry("Add to &Playlist", GlobalMsg::TryAddToPlaylist)
+            .separator()
+            .entry("&Open Playlist", GlobalMsg::TryOpenPlaylist)
+            .entry("Save Playlist &As", GlobalMsg::TrySavePlaylist);
+        })
+        .menu(playback, |menu| {
+          menu
+            .entry("Step &Forward", GlobalMsg::StepForward)
+            .entry("Step &Backward", GlobalMsg::StepBackward)
+            .separator()
+            .entry("0.25x", GlobalMsg::SetRate(0.25))
+            .entry("0.5x", GlobalMsg::SetRate(0.5))
+            .entry("&Normal Speed", GlobalMsg::SetRate(1.0))
+            .entry("1.5x", GlobalMsg::SetRate(1.5))
+            .entry("2x", GlobalMsg::SetRate(2.0))
+            .entry("4x", GlobalMsg::SetRate(4.0))
+            .entry("&Reverse", GlobalMsg::SetRate(-1.0))
+            .separator()
+            .item(Box::new(
+              MenuToggle::new_on("&Loop", |mgr, state| mgr.push(Msg::Loop(state)))
+                .with_state(self.loop_all),
+            ))
+            .entry("Mark &In", GlobalMsg::MarkIn)
+            .entry("Mark &Out", GlobalMsg::MarkOut)
+            .entry("&Clear A-B", GlobalMsg::ClearMarks)
+            .separator()
+            .item(Box::new(
+              MenuToggle::new_on("Preserve &Pitch", |mgr, state| {
+                mgr.push(Msg::Scaletempo(state))
+              })
+              .with_state(self.scaletempo),
+            ));
+        })
+        .menu(audio, |mut menu| {
+          menu.push_entry("Volume &Up", GlobalMsg::ChangeVolume(0.1));
+          menu.push_entry("Volume &Down", GlobalMsg::ChangeVolume(-0.1));
+          menu.push_item(Box::new(
+            MenuToggle::new_on("&Mute", |mgr, state| mgr.push(Msg::Mute(state)))
+              .with_state(self.muted),
+          ));
+          if !self.audio_tracks.is_empty() {
+            menu.push_separator();
+          }
+          let group = RadioGroup::new();
+          for (i, track) in self.audio_tracks.iter().enumerate() {
+            let label = format!("&{} {}", i + 1, track.replace('&', "&&"));
+            let radio = MenuRadio::new_on(label, group.clone(), move |mgr| {
+              mgr.push(Msg::AudioTrack(i))
+            });
+            menu.push_item(Box::new(radio.with_state(self.current_audio == Some(i))));
+          }
+        })
+        .menu(subtitles, |mut menu| {
+          menu.push_entry("&Load Subtitle File...", GlobalMsg::TryLoadSubtitle);
+          menu.push_entry("&Unload Subtitle File", GlobalMsg::RemoveSubtitle);
+          menu.push_entry("&Bigger Text", GlobalMsg::ScaleSubtitle(1.25));
+          menu.push_entry("&Smaller Text", GlobalMsg::ScaleSubtitle(0.8));
+          menu.push_item(Box::new(
+            MenuToggle::new_on("Outli&ne", |mgr, state| mgr.push(Msg::SubtitleOutline(state)))
+              .with_state(self.subtitle_outline),
+          ));
+          menu.push_item(Box::new(
+            MenuToggle::new_on("At &Top", |mgr, state| mgr.push(Msg::SubtitleTop(state)))
+              .with_state(self.subtitle_top),
+          ));
+          menu.push_separator();
+          let group = RadioGroup::new();
+          let off = MenuRadio::new_on("&Off", group.clone(), |mgr| mgr.push(Msg::TextTrack(None)));
+          menu.push_item(Box::new(off.with_state(self.current_text.is_none())));
+          for (i, track) in self.text_tracks.iter().enumerate() {
+            let label = format!("&{} {}", i + 1, track.replace('&', "&&"));
+            let radio = MenuRadio::new_on(label, group.clone(), move |mgr| {
+              mgr.push(Msg::TextTrack(Some(i)))
+            });
+            menu.push_item(Box::new(radio.with_state(self.current_text == Some(i))));
+          }
+        })
+        .menu(view, |mut menu| {
+          let group = RadioGroup::new();
+          let modes = [
+            ("&Fit", DisplayMode::Fit),
+            ("F&ill", DisplayMode::Fill),
+            ("&Stretch", DisplayMode::Stretch),
+            ("&Original Size", DisplayMode::Original),
+          ];
+          for (label, mode) in modes {
+            let radio = MenuRadio::new_on(label, group.clone(), move |mgr| mgr.push(Msg::DisplayMode(mode)));
+            menu.push_item(Box::new(radio.with_state(self.display_mode == mode)));
+          }
+          menu.push_entry("&Reset View", GlobalMsg::ResetView);
+          menu.push_submenu("Scaling &Quality", |mut menu| {
+            let group = RadioGroup::new();
+            let qualities = [
+              ("&Nearest", FilterQuality::Nearest),
+              ("Bi&linear", FilterQuality::Bilinear),
+              ("Bi&cubic", FilterQuality::Bicubic),
+            ];
+            for (label, quality) in qualities {
+              let radio = MenuRadio::new_on(label, group.clone(), move |mgr| {
+                mgr.push(Msg::FilterQuality(quality))
+              });
+              menu.push_item(Box::new(radio.with_state(self.filter_quality == quality)));
+            }
+          });
+          menu.push_separator();
+          menu.push_submenu("&Visualization", |mut menu| {
+            let group = RadioGroup::new();
+            let off = MenuRadio::new_on("&Off", group.clone(), |mgr| mgr.push(Msg::Visualization(None)));
+            menu.push_item(Box::new(off.with_state(self.visualization.is_none())));
+            for vis in &self.visualizers {
+              let name = vis.name.clone();
+              let radio = MenuRadio::new_on(vis.longname.replace('&', "&&"), group.clone(), move |mgr| {
+                mgr.push(Msg::Visualization(Some(name.clone())))
+              });
+              menu.push_item(Box::new(radio.with_state(self.visualization.as_ref() == Some(&vis.name))));
+            }
+          });
+          menu.push_separator();
+          menu.push_submenu("&Colours", |mut menu| {
+            let group = RadioGroup::new();
+            for name in COLOUR_SCHEMES {
+              let radio = MenuRadio::new_on(*name, group.clone(), move |mgr| mgr.push(Msg::Theme(name.to_string())));
+              menu.push_item(Box::new(radio.with_state(self.theme.as_deref() == Some(*name))));
+            }
+          });
+          menu.push_entry("Larger &Text", GlobalMsg::ChangeFontSize(2.0));
+          menu.push_entry("Smaller Te&xt", GlobalMsg::ChangeFontSize(-2.0));
+        })
+        .menu(help, |menu| {
+          menu.entry("&Shortcuts", GlobalMsg::ShowShortcuts);
+        })
+        .build()
+    }
+
+    /// Whether `id` is the one of a menu of the bar, rather than of an entry.
+    fn is_title(&self, id: &WidgetId) -> bool {
+      let index = self.display.find_child_index(id);
+      let child = index.and_then(|index| self.display.get_child(index));
+      child.is_some_and(|w| w.id_ref() == id)
+    }
+
+    /// Open the menu whose title has the accelerator `key`, if any.
+    ///
+    /// For the keys taken first by the player's shortcuts, when Alt is held.
+    pub fn open(&mut self, mgr: &mut EventMgr, key: VK) {
+      let index = TITLES.iter().position(|title| AccelString::from(*title).keys().contains(&key));
+      if let Some(id) = index.and_then(|index| self.display.get_child(index)).map(|w| w.id()) {
+        mgr.send(self, id, Event::Command(Command::Activate));
       }
     }
+
+    /// List the audio tracks of the media, `current` being checked.
+    pub fn set_audio_tracks(&mut self, tracks: Vec<String>, current: Option<usize>) -> Action {
+      if tracks == self.audio_tracks && current == self.current_audio {
+        return Action::empty();
+      }
+      self.audio_tracks = tracks;
+      self.current_audio = current;
+      self.display = self.build();
+      Action::RECONFIGURE
+    }
+
+    /// Check the mute item, when muting by the keyboard.
+    pub fn set_muted(&mut self, state: bool) -> Action {
+      if state == self.muted {
+        return Action::empty();
+      }
+      self.muted = state;
+      self.display = self.build();
+      Action::RECONFIGURE
+    }
+
+    /// List the subtitle tracks of the media, `current` being checked or else "Off".
+    pub fn set_text_tracks(&mut self, tracks: Vec<String>, current: Option<usize>) -> Action {
+      if tracks == self.text_tracks && current == self.current_text {
+        return Action::empty();
+      }
+      self.text_tracks = tracks;
+      self.current_text = current;
+      self.display = self.build();
+      Action::RECONFIGURE
+    }
   }
   impl Widget for Self {
+    // the window's alt-bypass is for the player's keys: the menus of the bar need Alt
+    fn steal_event(&mut self, mgr: &mut EventMgr, id: &WidgetId, event: &Event) -> Response {
+      let by_key = *event == Event::Command(Command::Activate) && mgr.modifiers() != ModifiersState::ALT;
+      if by_key && self.is_title(id) {
+        Response::Used
+      } else {
+        Response::Unused
+      }
+    }
+
     fn handle_message(&mut self, mgr: &mut EventMgr) {
-      if let Some(_msg) = mgr.try_pop::<Msg>() {
+      if let Some(msg) = mgr.try_pop::<Msg>() {
+        match msg {
+          Msg::Loop(state) => {
+            self.loop_all = state;
+            mgr.push(GlobalMsg::SetLoop(state));
+          }
+          Msg::Scaletempo(state) => {
+            self.scaletempo = state;
+            mgr.push(GlobalMsg::SetScaletempo(state));
+          }
+          Msg::Mute(state) => {
+            self.muted = state;
+            mgr.push(GlobalMsg::SetMute(state));
+          }
+          Msg::AudioTrack(index) => {
+            self.current_audio = Some(index);
+            mgr.push(GlobalMsg::SelectAudioTrack(index));
+          }
+          Msg::TextTrack(index) => {
+            self.current_text = index;
+            mgr.push(GlobalMsg::SelectTextTrack(index));
+          }
+          Msg::SubtitleOutline(state) => {
+            self.subtitle_outline = state;
+            mgr.push(GlobalMsg::SetSubtitleOutline(state));
+          }
+          Msg::SubtitleTop(state) => {
+            self.subtitle_top = state;
+            mgr.push(GlobalMsg::SetSubtitleTop(state));
+          }
+          Msg::Visualization(name) => {
+            self.visualization = name.clone();
+            mgr.push(GlobalMsg::SetVisualization(name));
+          }
+          Msg::DisplayMode(mode) => {
+            self.display_mode = mode;
+            mgr.push(GlobalMsg::SetDisplayMode(mode));
+          }
+          Msg::FilterQuality(quality) => {
+            self.filter_quality = quality;
+            mgr.push(GlobalMsg::SetFilterQuality(quality));
+          }
+          Msg::Theme(name) => {
+            self.theme = Some(name.clone());
+            mgr.push(GlobalMsg::SetTheme(name));
+          }
+        }
       }
     }
   }
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use kas::prelude::*;
+use kas::widgets::{Label, TextButton};
+use log::error;
+
+use super::GlobalMsg;
+
+/// Step of the keyboard nudges, in milliseconds.
+pub const STEP: i64 = 50;
+
+/// Delays of the audio and of the subtitles relative to the video, in milliseconds.
+///
+/// Negative values play them earlier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Offsets {
+    pub audio: i64,
+    pub subtitle: i64,
+}
+
+/// Offsets of every file they were changed for, saved under the XDG data directory.
+#[derive(Debug, Default)]
+pub struct Store {
+    path: Option<PathBuf>,
+    entries: HashMap<String, Offsets>,
+}
+
+impl Store {
+    /// Read the saved offsets. Lines are `audio<TAB>subtitle<TAB>uri`.
+    pub fn load() -> Self {
+        let path = dirs::data_dir().map(|d| d.join("kas-gstreamer-etude").join("offsets"));
+        let mut entries = HashMap::new();
+        if let Some(text) = path.as_ref().and_then(|p| std::fs::read_to_string(p).ok()) {
+            for line in text.lines() {
+                let mut fields = line.splitn(3, '\t');
+                let (Some(audio), Some(subtitle), Some(uri)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    continue;
+                };
+                if let (Ok(audio), Ok(subtitle)) = (audio.parse(), subtitle.parse()) {
+                    entries.insert(uri.to_string(), Offsets { audio, subtitle });
+                }
+            }
+        }
+        Store { path, entries }
+    }
+
+    pub fn get(&self, uri: &url::Url) -> Offsets {
+        self.entries.get(uri.as_str()).copied().unwrap_or_default()
+    }
+
+    /// Remember the offsets of `uri` and write the store.
+    pub fn set(&mut self, uri: &url::Url, offsets: Offsets) {
+        if offsets == Offsets::default() {
+            self.entries.remove(uri.as_str());
+        } else {
+            self.entries.insert(uri.to_string(), offsets);
+        }
+        if let Err(e) = self.save() {
+            error!("failed to save the offsets: {e}");
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut text = String::new();
+        for (uri, offsets) in &self.entries {
+            text += &format!("{}\t{}\t{}\n", offsets.audio, offsets.subtitle, uri);
+        }
+        std::fs::write(path, text)
+    }
+}
+
+fn format_offset(ms: i64) -> String {
+    format!("{:+.2}s", ms as f64 / 1000.0)
+}
+
+impl_scope! {
+    #[widget{
+        layout = row: [
+            self.audio_label,
+            self.audio_earlier,
+            self.audio,
+            self.audio_later,
+            self.subtitle_label,
+            self.subtitle_earlier,
+            self.subtitle,
+            self.subtitle_later,
+        ];
+    }]
+    #[derive(Debug)]
+    pub struct OffsetBar {
+        core: widget_core!(),
+        #[widget] audio_label: Label<&'static str>,
+        #[widget] audio_earlier: TextButton,
+        #[widget] audio: Label<String>,
+        #[widget] audio_later: TextButton,
+        #[widget] subtitle_label: Label<&'static str>,
+        #[widget] subtitle_earlier: TextButton,
+        #[widget] subtitle: Label<String>,
+        #[widget] subtitle_later: TextButton,
+    }
+
+    impl Self {
+        pub fn new() -> Self {
+            Self {
+                core: Default::default(),
+                audio_label: Label::new("audio"),
+                audio_earlier: TextButton::new_msg("-", GlobalMsg::NudgeAudioDelay(-STEP)),
+                audio: Label::new(format_offset(0)),
+                audio_later: TextButton::new_msg("+", GlobalMsg::NudgeAudioDelay(STEP)),
+                subtitle_label: Label::new("subtitles"),
+                subtitle_earlier: TextButton::new_msg("-", GlobalMsg::NudgeSubtitleDelay(-STEP)),
+                subtitle: Label::new(format_offset(0)),
+                subtitle_later: TextButton::new_msg("+", GlobalMsg::NudgeSubtitleDelay(STEP)),
+            }
+        }
+
+        pub fn set_offsets(&mut self, offsets: Offsets) -> Action {
+            let mut action = Action::empty();
+            let audio = format_offset(offsets.audio);
+            if self.audio.text().text() != &audio {
+                action |= self.audio.set_text(audio);
+            }
+            let subtitle = format_offset(offsets.subtitle);
+            if self.subtitle.text().text() != &subtitle {
+                action |= self.subtitle.set_text(subtitle);
+            }
+            action
+        }
+    }
+}
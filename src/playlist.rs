@@ -0,0 +1,170 @@
+use kas::prelude::*;
+use kas::widgets::{CheckButton, List, ScrollRegion, TextButton};
+use rand::seq::SliceRandom;
+
+use super::GlobalMsg;
+
+/// Ordered list of media to play, shown by [`Panel`].
+#[derive(Debug, Default)]
+pub struct Playlist {
+    items: Vec<url::Url>,
+    // play order as indices into `items`, shuffled or not
+    order: Vec<usize>,
+    // index into `order`
+    current: Option<usize>,
+    shuffle: bool,
+    repeat_all: bool,
+}
+
+impl Playlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn items(&self) -> &[url::Url] {
+        &self.items
+    }
+
+    /// Index in [`Playlist::items`] of the item being played.
+    pub fn current_index(&self) -> Option<usize> {
+        self.current.map(|p| self.order[p])
+    }
+
+    pub fn current(&self) -> Option<&url::Url> {
+        self.current_index().map(|i| &self.items[i])
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.order.clear();
+        self.current = None;
+    }
+
+    /// Append items. When shuffling, they are played in random order after the queued ones.
+    pub fn extend<I: IntoIterator<Item = url::Url>>(&mut self, urls: I) {
+        let start = self.items.len();
+        self.items.extend(urls);
+        let mut added: Vec<usize> = (start..self.items.len()).collect();
+        if self.shuffle {
+            added.shuffle(&mut rand::thread_rng());
+        }
+        self.order.extend(added);
+    }
+
+    /// Make the item at `index` in [`Playlist::items`] the current one.
+    pub fn select(&mut self, index: usize) -> Option<&url::Url> {
+        self.current = self.order.iter().position(|&i| i == index);
+        self.current()
+    }
+
+    fn next_position(&self) -> Option<usize> {
+        let next = self.current.map_or(0, |p| p + 1);
+        if next < self.order.len() {
+            Some(next)
+        } else if self.repeat_all && !self.order.is_empty() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// The item after the current one, without moving to it.
+    pub fn peek_next(&self) -> Option<&url::Url> {
+        self.next_position().map(|p| &self.items[self.order[p]])
+    }
+
+    pub fn next_item(&mut self) -> Option<&url::Url> {
+        self.current = Some(self.next_position()?);
+        self.current()
+    }
+
+    pub fn previous_item(&mut self) -> Option<&url::Url> {
+        let previous = match self.current {
+            Some(p) if p > 0 => p - 1,
+            _ if self.repeat_all && !self.order.is_empty() => self.order.len() - 1,
+            _ => return None,
+        };
+        self.current = Some(previous);
+        self.current()
+    }
+
+    /// Shuffle or restore the play order. The current item stays current.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        let current = self.current_index();
+        self.shuffle = shuffle;
+        self.order = (0..self.items.len()).collect();
+        if shuffle {
+            self.order.shuffle(&mut rand::thread_rng());
+            if let Some(p) = current.and_then(|c| self.order.iter().position(|&i| i == c)) {
+                self.order.swap(0, p);
+            }
+        }
+        self.current = current.and_then(|c| self.order.iter().position(|&i| i == c));
+    }
+
+    pub fn set_repeat_all(&mut self, repeat_all: bool) {
+        self.repeat_all = repeat_all;
+    }
+}
+
+fn item_label(url: &url::Url) -> String {
+    let name = match url.to_file_path() {
+        Ok(path) => path.file_name().map_or_else(
+            || path.to_string_lossy().into_owned(),
+            |n| n.to_string_lossy().into_owned(),
+        ),
+        Err(_) => url.to_string(),
+    };
+    // a single '&' would mark an accelerator key
+    name.replace('&', "&&")
+}
+
+impl_scope! {
+    #[widget{
+        layout = column: [
+            row: [self.previous, self.next],
+            self.shuffle,
+            self.repeat_all,
+            self.items,
+        ];
+    }]
+    #[derive(Debug)]
+    pub struct Panel {
+        core: widget_core!(),
+        #[widget] previous: TextButton,
+        #[widget] next: TextButton,
+        #[widget] shuffle: CheckButton,
+        #[widget] repeat_all: CheckButton,
+        #[widget] items: ScrollRegion<List<kas::dir::Down, TextButton>>,
+    }
+
+    impl Self {
+        pub fn new() -> Self {
+            Self {
+                core: Default::default(),
+                previous: TextButton::new_msg("Previous", GlobalMsg::PlaylistPrevious),
+                next: TextButton::new_msg("Next", GlobalMsg::PlaylistNext),
+                shuffle: CheckButton::new_on("Shuffle", |mgr, state| {
+                    mgr.push(GlobalMsg::SetShuffle(state))
+                }),
+                repeat_all: CheckButton::new_on("Repeat all", |mgr, state| {
+                    mgr.push(GlobalMsg::SetRepeatAll(state))
+                }),
+                items: ScrollRegion::new(List::new()),
+            }
+        }
+
+        /// Rebuild the list of items, marking the current one.
+        pub fn set_playlist(&mut self, playlist: &Playlist) -> Action {
+            let current = playlist.current_index();
+            self.items.inner_mut().edit(|buttons| {
+                buttons.clear();
+                for (i, url) in playlist.items().iter().enumerate() {
+                    let mark = if Some(i) == current { "> " } else { "  " };
+                    let label = format!("{mark}{}", item_label(url));
+                    buttons.push(TextButton::new_msg(label, GlobalMsg::PlayIndex(i)));
+                }
+            })
+        }
+    }
+}
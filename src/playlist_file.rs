@@ -0,0 +1,266 @@
+use std::io::Write;
+use std::path::Path;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use thiserror::Error;
+
+use super::file_url;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Xml(#[from] quick_xml::Error),
+    #[error("unknown playlist format: {0}")]
+    Format(String),
+}
+
+/// Playlist file formats, chosen by the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Plain or extended M3U, `.m3u8` being the same in UTF-8.
+    M3u,
+    Pls,
+    Xspf,
+}
+
+impl Format {
+    pub const EXTENSIONS: &'static [&'static str] = &["m3u", "m3u8", "pls", "xspf"];
+
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        match ext.as_deref() {
+            Some("m3u") | Some("m3u8") => Ok(Format::M3u),
+            Some("pls") => Ok(Format::Pls),
+            Some("xspf") => Ok(Format::Xspf),
+            _ => Err(Error::Format(path.display().to_string())),
+        }
+    }
+}
+
+/// Resolve an entry of a playlist stored in `dir`.
+///
+/// Entries with a scheme are taken as they are, others are paths relative to the playlist.
+fn resolve(dir: &Path, entry: &str) -> Option<url::Url> {
+    let entry = entry.trim();
+    if entry.is_empty() {
+        return None;
+    }
+    match url::Url::parse(entry) {
+        // a single letter is a windows drive, not a scheme
+        Ok(url) if url.scheme().len() > 1 => Some(url),
+        _ => Some(file_url(&dir.join(entry))),
+    }
+}
+
+/// Write `url` as a path relative to `dir` when possible.
+fn unresolve(dir: &Path, url: &url::Url) -> String {
+    match url.to_file_path() {
+        Ok(path) => match path.strip_prefix(dir) {
+            Ok(relative) => relative.to_string_lossy().into_owned(),
+            Err(_) => path.to_string_lossy().into_owned(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+fn parse_m3u(dir: &Path, text: &str) -> Vec<url::Url> {
+    // .m3u8 files are often written with a BOM, which would stick to the first line
+    text.trim_start_matches('\u{feff}')
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| resolve(dir, line))
+        .collect()
+}
+
+fn parse_pls(dir: &Path, text: &str) -> Vec<url::Url> {
+    let mut entries: Vec<(usize, url::Url)> = text
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            // keys are case-insensitive in practice, `file1=` is common
+            let key = key.trim();
+            if !key.get(..4)?.eq_ignore_ascii_case("file") {
+                return None;
+            }
+            let n = key[4..].parse::<usize>().ok()?;
+            Some((n, resolve(dir, value)?))
+        })
+        .collect();
+    entries.sort_by_key(|(n, _)| *n);
+    entries.into_iter().map(|(_, url)| url).collect()
+}
+
+fn parse_xspf(dir: &Path, text: &str) -> Result<Vec<url::Url>, Error> {
+    // locations are URIs, relative ones are resolved against the playlist
+    let base = url::Url::from_directory_path(dir).unwrap_or_else(|_| file_url(&dir.join("")));
+    let mut reader = Reader::from_str(text);
+    reader.trim_text(true);
+    let mut urls = Vec::new();
+    let mut in_location = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == b"location" => in_location = true,
+            Event::End(e) if e.local_name().as_ref() == b"location" => in_location = false,
+            Event::Text(e) if in_location => {
+                let location = e.unescape()?;
+                match base.join(location.trim()) {
+                    Ok(url) => urls.push(url),
+                    Err(e) => log::error!("bad location {}: {}", location, e),
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    Ok(urls)
+}
+
+/// Read the entries of the playlist file at `path`.
+pub fn load(path: &Path) -> Result<Vec<url::Url>, Error> {
+    let format = Format::from_path(path)?;
+    let bytes = std::fs::read(path)?;
+    // legacy .m3u may be latin-1, keep whatever decodes
+    let text = String::from_utf8_lossy(&bytes);
+    let dir = path.parent().unwrap_or(Path::new(""));
+    match format {
+        Format::M3u => Ok(parse_m3u(dir, &text)),
+        Format::Pls => Ok(parse_pls(dir, &text)),
+        Format::Xspf => parse_xspf(dir, &text),
+    }
+}
+
+/// Write `urls` to the playlist file at `path`, in the format of its extension.
+pub fn save(path: &Path, urls: &[url::Url]) -> Result<(), Error> {
+    let format = Format::from_path(path)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut out = Vec::new();
+    match format {
+        Format::M3u => {
+            writeln!(out, "#EXTM3U")?;
+            for url in urls {
+                writeln!(out, "{}", unresolve(dir, url))?;
+            }
+        }
+        Format::Pls => {
+            writeln!(out, "[playlist]")?;
+            for (i, url) in urls.iter().enumerate() {
+                writeln!(out, "File{}={}", i + 1, unresolve(dir, url))?;
+            }
+            writeln!(out, "NumberOfEntries={}", urls.len())?;
+            writeln!(out, "Version=2")?;
+        }
+        Format::Xspf => {
+            writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+            writeln!(
+                out,
+                r#"<playlist version="1" xmlns="http://xspf.org/ns/0/">"#
+            )?;
+            writeln!(out, "  <trackList>")?;
+            for url in urls {
+                let location = quick_xml::escape::escape(url.as_str());
+                writeln!(out, "    <track><location>{}</location></track>", location)?;
+            }
+            writeln!(out, "  </trackList>")?;
+            writeln!(out, "</playlist>")?;
+        }
+    }
+    std::fs::write(path, out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> url::Url {
+        url::Url::parse(s).unwrap()
+    }
+
+    fn test_dir() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("playlist-test-{}", std::process::id()))
+    }
+
+    /// Save `urls` to a playlist named `name`, under the directory of [`sample`]'s files.
+    fn round_trip(name: &str, urls: &[url::Url]) -> Vec<url::Url> {
+        let dir = test_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        save(&path, urls).unwrap();
+        let loaded = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    fn sample(dir: &Path) -> Vec<url::Url> {
+        vec![
+            file_url(&dir.join("a.mp4")),
+            file_url(&dir.join("sub dir/b c.mkv")),
+            file_url(&dir.join("été #1.mp3")),
+            file_url(Path::new("/elsewhere/d.ogg")),
+            url("http://example.com/live%20stream?x=1&y=2"),
+        ]
+    }
+
+    #[test]
+    fn m3u_round_trip() {
+        let urls = sample(&test_dir());
+        assert_eq!(round_trip("list.m3u8", &urls), urls);
+    }
+
+    #[test]
+    fn pls_round_trip() {
+        let urls = sample(&test_dir());
+        assert_eq!(round_trip("list.pls", &urls), urls);
+    }
+
+    #[test]
+    fn xspf_round_trip() {
+        let urls = sample(&test_dir());
+        assert_eq!(round_trip("list.xspf", &urls), urls);
+    }
+
+    #[test]
+    fn m3u_relative_paths_and_bom() {
+        let text =
+            "\u{feff}#EXTM3U\n#EXTINF:10,Title\nb c.mp4\n\nsub/d.mp3\nhttp://example.com/e.ogg\n";
+        let urls = parse_m3u(Path::new("/music"), text);
+        assert_eq!(
+            urls,
+            vec![
+                url("file:///music/b%20c.mp4"),
+                url("file:///music/sub/d.mp3"),
+                url("http://example.com/e.ogg"),
+            ]
+        );
+    }
+
+    #[test]
+    fn pls_keys_in_any_case_and_order() {
+        let text = "[playlist]\nfile2=b.mp3\nFILE1=a.mp3\nTitle1=A\nNumberOfEntries=2\n";
+        let urls = parse_pls(Path::new("/music"), text);
+        assert_eq!(
+            urls,
+            vec![url("file:///music/a.mp3"), url("file:///music/b.mp3")]
+        );
+    }
+
+    #[test]
+    fn xspf_escaped_locations() {
+        let text = r#"<?xml version="1.0"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/"><trackList>
+<track><location>b%20c.mp4</location></track>
+<track><location>http://example.com/?a=1&amp;b=2</location></track>
+</trackList></playlist>"#;
+        let urls = parse_xspf(Path::new("/music"), text).unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                url("file:///music/b%20c.mp4"),
+                url("http://example.com/?a=1&b=2")
+            ]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use super::image::DisplayMode;
+
+/// Colour schemes of the theme, see `kas::theme::Config`.
+pub const COLOUR_SCHEMES: &[&str] = &["light", "dark", "blue"];
+
+/// Font sizes the text can be set to, in points.
+pub const FONT_SIZE_RANGE: std::ops::RangeInclusive<f32> = 8.0..=48.0;
+
+/// Preferences of the user, saved under the XDG config directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Where the file dialogs open, the directory of the media opened last.
+    pub last_directory: Option<PathBuf>,
+    pub volume: f64,
+    pub muted: bool,
+    /// Colour scheme, the theme's own when unset.
+    pub theme: Option<String>,
+    pub font_size: f32,
+    /// Size of the contents of the window in physical pixels, their ideal one when unset.
+    ///
+    /// The position of the window isn't saved: kas 0.13 can neither read nor set it.
+    pub window_size: Option<(u32, u32)>,
+    pub display_mode: DisplayMode,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            last_directory: None,
+            volume: 1.0,
+            muted: false,
+            theme: None,
+            font_size: 24.0,
+            window_size: None,
+            display_mode: DisplayMode::default(),
+            path: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Read the settings, the defaults when there are none yet.
+    pub fn load() -> Self {
+        let path = dirs::config_dir().map(|d| d.join("kas-gstreamer-etude").join("settings.toml"));
+        let mut settings = match path.as_ref().and_then(|p| std::fs::read_to_string(p).ok()) {
+            None => Settings::default(),
+            Some(text) => toml::from_str(&text).unwrap_or_else(|e| {
+                error!("{}: {}", path.as_ref().unwrap().display(), e);
+                Settings::default()
+            }),
+        };
+        settings.volume = settings.volume.clamp(0.0, 1.0);
+        settings.font_size = settings
+            .font_size
+            .clamp(*FONT_SIZE_RANGE.start(), *FONT_SIZE_RANGE.end());
+        settings.path = path;
+        settings
+    }
+
+    /// Directory where the file dialogs open.
+    pub fn dialog_directory(&self) -> PathBuf {
+        match self.last_directory {
+            Some(ref dir) if dir.is_dir() => dir.clone(),
+            _ => dirs::home_dir().unwrap_or_else(|| PathBuf::from("/")),
+        }
+    }
+
+    /// Change the settings with `f` and write them when they changed.
+    pub fn update<F: FnOnce(&mut Settings)>(&mut self, f: F) {
+        let old = self.clone();
+        f(self);
+        if *self != old {
+            if let Err(e) = self.save() {
+                error!("failed to save the settings: {e}");
+            }
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, text)
+    }
+}
+
+/// The directory of a `file://` url, for [`Settings::last_directory`].
+pub fn directory_of(url: &url::Url) -> Option<PathBuf> {
+    let path = url.to_file_path().ok()?;
+    path.parent().map(Path::to_path_buf)
+}
@@ -0,0 +1,67 @@
+use kas::event::{Command, VirtualKeyCode as VK};
+use kas::prelude::*;
+use kas::widgets::Row;
+
+use super::bindings::Bindings;
+
+/// A bound key was pressed, with the modifiers of `EventState::modifiers`.
+#[derive(Clone, Copy, Debug)]
+pub struct KeyPress(pub VK);
+
+impl_scope! {
+    /// An invisible widget pushing a [`KeyPress`] when its key is pressed.
+    ///
+    /// Letter keys reach widgets only through accelerators, so each key registers its own.
+    #[derive(Debug)]
+    #[widget]
+    pub struct Shortcut {
+        core: widget_core!(),
+        key: VK,
+    }
+
+    impl Self {
+        pub fn new(key: VK) -> Self {
+            Shortcut {
+                core: Default::default(),
+                key,
+            }
+        }
+    }
+
+    impl Layout for Self {
+        fn size_rules(&mut self, _: SizeMgr, _: AxisInfo) -> SizeRules {
+            SizeRules::EMPTY
+        }
+
+        fn draw(&mut self, _: DrawMgr) {}
+    }
+
+    impl Widget for Self {
+        fn configure(&mut self, mgr: &mut ConfigMgr) {
+            mgr.add_accel_keys(self.id_ref(), &[self.key]);
+        }
+
+        fn handle_event(&mut self, mgr: &mut EventMgr, event: Event) -> Response {
+            match event {
+                Event::Command(Command::Activate) => {
+                    mgr.push(KeyPress(self.key));
+                    Response::Used
+                }
+                _ => Response::Unused,
+            }
+        }
+    }
+}
+
+/// The shortcuts of the keys bound as accelerators.
+///
+/// The other chords are commands, handled by `Main` as the navigation fallback.
+pub fn accelerators(bindings: &Bindings) -> Row<Shortcut> {
+    Row::new_vec(
+        bindings
+            .accel_keys()
+            .into_iter()
+            .map(Shortcut::new)
+            .collect(),
+    )
+}
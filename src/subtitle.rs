@@ -0,0 +1,610 @@
+use std::path::Path;
+use std::time::Duration;
+
+use ab_glyph::{Font, FontVec, GlyphId, OutlineCurve};
+use kas::resvg::tiny_skia::{
+    FillRule, LineJoin, Paint, Path as SkiaPath, PathBuilder, Pixmap, Rect, Stroke, Transform,
+};
+use log::info;
+use thiserror::Error;
+
+/// Horizontal shear of the synthesized italics.
+const ITALIC_SKEW: f32 = 0.2;
+/// Stroke width of the synthesized bold, relative to the font size.
+const BOLD_WEIGHT: f32 = 0.04;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("no font to draw subtitles with")]
+    Font,
+}
+
+/// A run of text sharing the same style.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    /// `None` for the default color.
+    pub color: Option<[u8; 3]>,
+}
+
+impl Span {
+    fn same_style(&self, other: &Span) -> bool {
+        self.bold == other.bold
+            && self.italic == other.italic
+            && self.underline == other.underline
+            && self.color == other.color
+    }
+}
+
+/// Text shown from `start` until `end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cue {
+    pub start: Duration,
+    pub end: Duration,
+    pub lines: Vec<Vec<Span>>,
+}
+
+/// The cues of a subtitle file, ordered by start time.
+#[derive(Debug, Clone, Default)]
+pub struct Subtitles {
+    cues: Vec<Cue>,
+}
+
+impl Subtitles {
+    pub fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    /// Indices of the cues shown at `position`.
+    pub fn active(&self, position: Duration) -> Vec<usize> {
+        // cues starting later can't be shown yet
+        let n = self.cues.partition_point(|c| c.start <= position);
+        (0..n).filter(|&i| position < self.cues[i].end).collect()
+    }
+}
+
+/// Read an SRT or WebVTT file, told apart by the `WEBVTT` header.
+pub fn load(path: &Path) -> Result<Subtitles, Error> {
+    let bytes = std::fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let subtitles = parse(&text);
+    info!("{} cues in {}", subtitles.cues.len(), path.display());
+    Ok(subtitles)
+}
+
+pub fn parse(text: &str) -> Subtitles {
+    let text = text.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let vtt = text.starts_with("WEBVTT");
+    let mut cues: Vec<Cue> = blocks(&text)
+        .into_iter()
+        .filter(|block| {
+            // the header and the blocks which are not cues
+            !(vtt
+                && ["WEBVTT", "NOTE", "STYLE", "REGION"]
+                    .iter()
+                    .any(|k| block[0].starts_with(k)))
+        })
+        .filter_map(|block| parse_cue(&block))
+        .collect();
+    cues.sort_by_key(|c| c.start);
+    Subtitles { cues }
+}
+
+fn blocks(text: &str) -> Vec<Vec<&str>> {
+    let mut blocks = vec![];
+    let mut block = vec![];
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !block.is_empty() {
+                blocks.push(std::mem::take(&mut block));
+            }
+        } else {
+            block.push(line);
+        }
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+// An SRT counter or a WebVTT identifier may come before the timing line.
+fn parse_cue(block: &[&str]) -> Option<Cue> {
+    let timing = block.iter().position(|line| line.contains("-->"))?;
+    let (start, end) = parse_timing(block[timing])?;
+    let lines = block[timing + 1..]
+        .iter()
+        .map(|line| parse_line(line))
+        .collect();
+    Some(Cue { start, end, lines })
+}
+
+// WebVTT cue settings may follow the end time.
+fn parse_timing(line: &str) -> Option<(Duration, Duration)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// Parses `[hh:]mm:ss.ttt`, with a comma in SRT.
+fn parse_timestamp(s: &str) -> Option<Duration> {
+    let (hms, frac) = s.trim().rsplit_once(['.', ','])?;
+    let millis = format!("{frac:0<3}").get(..3)?.parse::<u64>().ok()?;
+    let parts: Vec<&str> = hms.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    // absurd hours overflow, the cue being skipped then
+    let mut secs: u64 = 0;
+    for part in parts {
+        secs = secs.checked_mul(60)?.checked_add(part.parse().ok()?)?;
+    }
+    Some(Duration::from_secs(secs) + Duration::from_millis(millis))
+}
+
+fn color_by_name(name: &str) -> Option<[u8; 3]> {
+    let color = match name {
+        "white" => [255, 255, 255],
+        "black" => [0, 0, 0],
+        "red" => [255, 0, 0],
+        "lime" => [0, 255, 0],
+        "green" => [0, 128, 0],
+        "blue" => [0, 0, 255],
+        "yellow" => [255, 255, 0],
+        "cyan" => [0, 255, 255],
+        "magenta" => [255, 0, 255],
+        "silver" => [192, 192, 192],
+        "gray" | "grey" => [128, 128, 128],
+        _ => return None,
+    };
+    Some(color)
+}
+
+fn parse_color(s: &str) -> Option<[u8; 3]> {
+    let s = s.trim().to_lowercase();
+    let Some(hex) = s.strip_prefix('#') else {
+        return color_by_name(&s);
+    };
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    match hex.len() {
+        6 => Some([channel(0)?, channel(2)?, channel(4)?]),
+        _ => None,
+    }
+}
+
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lrm;", "")
+        .replace("&rlm;", "")
+        .replace("&amp;", "&")
+}
+
+#[derive(Default)]
+struct Markup {
+    bold: usize,
+    italic: usize,
+    underline: usize,
+    colors: Vec<Option<[u8; 3]>>,
+}
+
+impl Markup {
+    // <b>, <i>, <u>, SRT's <font color=...> and WebVTT's color classes as in <c.yellow>
+    fn apply(&mut self, tag: &str) {
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '.')
+            .unwrap_or(tag.len());
+        let name = tag[..name_end].to_lowercase();
+        let count = match name.as_str() {
+            "b" => &mut self.bold,
+            "i" => &mut self.italic,
+            "u" => &mut self.underline,
+            "font" | "c" if closing => {
+                self.colors.pop();
+                return;
+            }
+            "font" => {
+                let color = tag.split_once("color=").and_then(|(_, v)| {
+                    let v = v.trim_start_matches(['"', '\'']);
+                    parse_color(&v[..v.find(['"', '\'', ' ']).unwrap_or(v.len())])
+                });
+                self.colors.push(color);
+                return;
+            }
+            "c" => {
+                let color = tag[name_end..].split('.').find_map(color_by_name);
+                self.colors.push(color);
+                return;
+            }
+            // voices, languages, rubies and timestamps
+            _ => return,
+        };
+        if closing {
+            *count = count.saturating_sub(1);
+        } else {
+            *count += 1;
+        }
+    }
+
+    fn span(&self, text: String) -> Span {
+        Span {
+            text,
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            underline: self.underline > 0,
+            color: self.colors.iter().rev().find_map(|c| *c),
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = vec![];
+    let mut markup = Markup::default();
+    let mut push = |markup: &Markup, text: &str| {
+        if text.is_empty() {
+            return;
+        }
+        let span = markup.span(decode_entities(text));
+        match spans.last_mut() {
+            Some(last) if last.same_style(&span) => last.text.push_str(&span.text),
+            _ => spans.push(span),
+        }
+    };
+    let mut rest = line;
+    while !rest.is_empty() {
+        // ASS override blocks such as {\an8} are often found in SRT files
+        let next = rest.find(['<', '{']).unwrap_or(rest.len());
+        push(&markup, &rest[..next]);
+        rest = &rest[next..];
+        if let Some(tag) = rest.strip_prefix('<') {
+            let Some(end) = tag.find('>') else {
+                push(&markup, rest);
+                break;
+            };
+            markup.apply(tag[..end].trim());
+            rest = &tag[end + 1..];
+        } else if rest.starts_with("{\\") {
+            match rest.find('}') {
+                Some(end) => rest = &rest[end + 1..],
+                None => break,
+            }
+        } else if !rest.is_empty() {
+            push(&markup, &rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    spans
+}
+
+/// Where the subtitles are placed in the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Bottom,
+    Top,
+}
+
+/// Appearance of the subtitles, independent of the frame size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// Text height as a fraction of the frame height.
+    pub font_size: f32,
+    /// Width of the black outline in pixels, 0 for none.
+    pub outline: f32,
+    pub placement: Placement,
+    /// Distance to the edge of the frame as a fraction of its height.
+    pub margin: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            font_size: 0.06,
+            outline: 2.0,
+            placement: Placement::Bottom,
+            margin: 0.05,
+        }
+    }
+}
+
+struct Placed<'a> {
+    id: GlyphId,
+    x: f32,
+    advance: f32,
+    span: &'a Span,
+}
+
+/// Draws cues with a system sans-serif font, synthesizing bold and italics.
+pub struct Renderer {
+    font: FontVec,
+}
+
+impl std::fmt::Debug for Renderer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "subtitle::Renderer")
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Result<Self, Error> {
+        let mut db = fontdb::Database::new();
+        db.load_system_fonts();
+        let families = [
+            fontdb::Family::Name("DejaVu Sans"),
+            fontdb::Family::Name("Noto Sans"),
+            fontdb::Family::Name("Liberation Sans"),
+            fontdb::Family::SansSerif,
+        ];
+        let query = fontdb::Query {
+            families: &families,
+            ..Default::default()
+        };
+        let id = db
+            .query(&query)
+            .or_else(|| db.faces().first().map(|face| face.id))
+            .ok_or(Error::Font)?;
+        let font = db
+            .with_face_data(id, |data, index| {
+                FontVec::try_from_vec_and_index(data.to_vec(), index)
+            })
+            .ok_or(Error::Font)?
+            .map_err(|_| Error::Font)?;
+        Ok(Renderer { font })
+    }
+
+    fn glyph_path(&self, id: GlyphId) -> Option<SkiaPath> {
+        let outline = self.font.outline(id)?;
+        let mut pb = PathBuilder::new();
+        let mut last = None;
+        for curve in outline.curves {
+            let (start, end) = match curve {
+                OutlineCurve::Line(p0, p1) => (p0, p1),
+                OutlineCurve::Quad(p0, _, p2) => (p0, p2),
+                OutlineCurve::Cubic(p0, _, _, p3) => (p0, p3),
+            };
+            // a new contour starts where the previous curve did not end
+            if last != Some(start) {
+                if last.is_some() {
+                    pb.close();
+                }
+                pb.move_to(start.x, start.y);
+            }
+            match curve {
+                OutlineCurve::Line(_, p1) => pb.line_to(p1.x, p1.y),
+                OutlineCurve::Quad(_, p1, p2) => pb.quad_to(p1.x, p1.y, p2.x, p2.y),
+                OutlineCurve::Cubic(_, p1, p2, p3) => {
+                    pb.cubic_to(p1.x, p1.y, p2.x, p2.y, p3.x, p3.y)
+                }
+            }
+            last = Some(end);
+        }
+        pb.close();
+        pb.finish()
+    }
+
+    fn layout<'a>(&self, line: &'a [Span], scale: f32) -> Vec<Placed<'a>> {
+        let mut placed = vec![];
+        let mut x = 0.0;
+        let mut previous = None;
+        for span in line {
+            for c in span.text.chars() {
+                let id = self.font.glyph_id(c);
+                if let Some(previous) = previous {
+                    x += self.font.kern_unscaled(previous, id) * scale;
+                }
+                let advance = self.font.h_advance_unscaled(id) * scale;
+                placed.push(Placed {
+                    id,
+                    x,
+                    advance,
+                    span,
+                });
+                x += advance;
+                previous = Some(id);
+            }
+        }
+        placed
+    }
+
+    /// Draw `cues` centered over a frame of `width` x `height` at the origin of `target`.
+    pub fn draw(&self, target: &mut Pixmap, width: u32, height: u32, cues: &[&Cue], style: &Style) {
+        let lines: Vec<&[Span]> = cues
+            .iter()
+            .flat_map(|cue| cue.lines.iter().map(|line| line.as_slice()))
+            .collect();
+        if lines.is_empty() {
+            return;
+        }
+        let px = style.font_size * height as f32;
+        let scale = px / self.font.height_unscaled();
+        let line_height = (self.font.height_unscaled() + self.font.line_gap_unscaled()) * scale;
+        let margin = style.margin * height as f32;
+        let top = match style.placement {
+            Placement::Bottom => height as f32 - margin - line_height * lines.len() as f32,
+            Placement::Top => margin,
+        };
+
+        let mut paint = Paint::default();
+        // stroke widths are in font units, as they are transformed with the glyphs
+        let outline = Stroke {
+            width: style.outline * 2.0 / scale,
+            line_join: LineJoin::Round,
+            ..Default::default()
+        };
+        let bold = Stroke {
+            width: BOLD_WEIGHT * px / scale,
+            line_join: LineJoin::Round,
+            ..Default::default()
+        };
+
+        for (i, line) in lines.iter().enumerate() {
+            let baseline = top + i as f32 * line_height + self.font.ascent_unscaled() * scale;
+            let glyphs = self.layout(line, scale);
+            let line_width = glyphs.last().map_or(0.0, |g| g.x + g.advance);
+            let left = (width as f32 - line_width) / 2.0;
+            let paths: Vec<_> = glyphs
+                .iter()
+                .map(|g| {
+                    let skew = if g.span.italic {
+                        ITALIC_SKEW * scale
+                    } else {
+                        0.0
+                    };
+                    let transform =
+                        Transform::from_row(scale, 0.0, skew, -scale, left + g.x, baseline);
+                    (self.glyph_path(g.id), transform)
+                })
+                .collect();
+
+            // all the outlines first, not to cover the neighbouring glyphs
+            if style.outline > 0.0 {
+                paint.set_color_rgba8(0, 0, 0, 255);
+                for (path, transform) in &paths {
+                    if let Some(path) = path {
+                        target.stroke_path(path, &paint, &outline, *transform, None);
+                    }
+                }
+            }
+            for (g, (path, transform)) in glyphs.iter().zip(&paths) {
+                let [r, gr, b] = g.span.color.unwrap_or([255, 255, 255]);
+                paint.set_color_rgba8(r, gr, b, 255);
+                if let Some(path) = path {
+                    target.fill_path(path, &paint, FillRule::Winding, *transform, None);
+                    if g.span.bold {
+                        target.stroke_path(path, &paint, &bold, *transform, None);
+                    }
+                }
+                if g.span.underline {
+                    let thickness = (px / 16.0).max(1.0);
+                    if let Some(rect) =
+                        Rect::from_xywh(left + g.x, baseline + px / 10.0, g.advance, thickness)
+                    {
+                        target.fill_rect(rect, &paint, Transform::identity(), None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn plain(text: &str) -> Vec<Span> {
+        vec![Span {
+            text: text.to_string(),
+            ..Default::default()
+        }]
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(parse_timestamp("00:00:01,500"), Some(ms(1_500)));
+        assert_eq!(parse_timestamp("01:02:03.004"), Some(ms(3_723_004)));
+        assert_eq!(parse_timestamp("02:03.4"), Some(ms(123_400)));
+        assert_eq!(parse_timestamp(" 00:00:00,000 "), Some(ms(0)));
+        assert_eq!(parse_timestamp("12"), None);
+        assert_eq!(parse_timestamp("1:2:3:4.000"), None);
+        assert_eq!(parse_timestamp("aa:bb.ccc"), None);
+        assert_eq!(parse_timestamp("1000000000000000000:00:00,000"), None);
+        assert_eq!(parse_timestamp("307445734561825860:16.000"), None);
+    }
+
+    #[test]
+    fn srt_with_bom_and_crlf() {
+        let text = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\n\r\n2\r\n00:00:03,000 --> 00:00:04,000\r\nWorld\r\n";
+        let cues = parse(text).cues;
+        assert_eq!(cues.len(), 2);
+        assert_eq!((cues[0].start, cues[0].end), (ms(1_000), ms(2_500)));
+        assert_eq!(cues[0].lines, vec![plain("Hello")]);
+        assert_eq!(cues[1].lines, vec![plain("World")]);
+    }
+
+    #[test]
+    fn vtt_multi_line_cues_and_settings() {
+        let text = "WEBVTT - title\n\nNOTE a comment\nover two lines\n\nSTYLE\n::cue { color: red }\n\n\
+                    intro\n00:01.000 --> 00:02.000 align:start line:0\nfirst line\n<i>second</i> line\n";
+        let cues = parse(text).cues;
+        assert_eq!(cues.len(), 1);
+        assert_eq!((cues[0].start, cues[0].end), (ms(1_000), ms(2_000)));
+        assert_eq!(cues[0].lines.len(), 2);
+        assert_eq!(cues[0].lines[0], plain("first line"));
+        let second = &cues[0].lines[1];
+        assert_eq!(
+            second[0],
+            Span {
+                text: "second".to_string(),
+                italic: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            second[1],
+            Span {
+                text: " line".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn bad_blocks_are_skipped() {
+        let text = "1\nno timing here\n\n2\n00:00:xx,000 --> 00:00:02,000\nbad start\n\n\
+                    3\n00:00:05,000 --> 00:00:06,000\nkept\n\n4\n00:00:03,000 --> 00:00:04,000\nearlier\n";
+        let cues = parse(text).cues;
+        let texts: Vec<_> = cues.iter().map(|c| c.lines[0][0].text.as_str()).collect();
+        // ordered by start time
+        assert_eq!(texts, ["earlier", "kept"]);
+    }
+
+    #[test]
+    fn markup() {
+        let spans = parse_line(
+            r##"{\an8}<b>bold <font color="#ff0000">red</font></b> &amp; <c.yellow>x</c>"##,
+        );
+        assert_eq!(
+            spans[0],
+            Span {
+                text: "bold ".to_string(),
+                bold: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(spans[1].text, "red");
+        assert_eq!((spans[1].bold, spans[1].color), (true, Some([255, 0, 0])));
+        assert_eq!(
+            spans[2],
+            Span {
+                text: " & ".to_string(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(spans[3].text, "x");
+        assert!(spans[3].color.is_some());
+    }
+
+    #[test]
+    fn active_cues() {
+        let subtitles =
+            parse("00:00:01.000 --> 00:00:03.000\na\n\n00:00:02.000 --> 00:00:04.000\nb\n");
+        assert_eq!(subtitles.active(ms(500)), Vec::<usize>::new());
+        assert_eq!(subtitles.active(ms(2_500)), vec![0, 1]);
+        assert_eq!(subtitles.active(ms(3_000)), vec![1]);
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+use kas::prelude::*;
+use kas::widgets::{Label, Slider};
+
+use super::video::Position;
+use super::GlobalMsg;
+
+/// Minimum interval between two seeks while the slider is dragged.
+const SCRUB_INTERVAL: Duration = Duration::from_millis(100);
+/// The accurate seek to the final position is done after the slider rests for this long.
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+const TIMER_SETTLE: u64 = 1;
+
+#[derive(Clone, Debug)]
+enum Msg {
+    Move(f64),
+}
+
+fn format_time(t: Duration) -> String {
+    let secs = t.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+impl_scope! {
+    #[widget{
+        layout = row: [
+            self.slider,
+            self.time,
+            self.frame,
+        ];
+    }]
+    #[derive(Debug)]
+    pub struct Timeline {
+        core: widget_core!(),
+        #[widget] slider: Slider<f64, kas::dir::Right>,
+        #[widget] time: Label<String>,
+        #[widget] frame: Label<String>,
+        duration: Option<Duration>,
+        live: bool,
+        seekable: bool,
+        last_scrub: Option<Instant>,
+        scrubbing: bool,
+    }
+
+    impl Self {
+        pub fn new() -> Self {
+            Self {
+                core: Default::default(),
+                slider: Slider::new_on(0.0..=1.0, 0.01, |mgr, v| mgr.push(Msg::Move(v))),
+                time: Label::new(Self::time_text(None, None, false)),
+                frame: Label::new(Self::frame_text(None)),
+                duration: None,
+                live: false,
+                seekable: true,
+                last_scrub: None,
+                scrubbing: false,
+            }
+        }
+
+        fn time_text(position: Option<Duration>, duration: Option<Duration>, live: bool) -> String {
+            let position = position.map_or("-:--:--".to_string(), format_time);
+            let duration = match duration {
+                _ if live => "live".to_string(),
+                Some(duration) => format_time(duration),
+                None => "-:--:--".to_string(),
+            };
+            format!("{position} / {duration}")
+        }
+
+        fn set_time_text(&mut self, position: Option<Duration>) -> Action {
+            let text = Self::time_text(position, self.duration, self.live);
+            if self.time.text().text() == &text {
+                return Action::empty();
+            }
+            self.time.set_text(text)
+        }
+
+        fn frame_text(frame: Option<u64>) -> String {
+            frame.map_or("#-".to_string(), |n| Position::Frame(n).to_string())
+        }
+
+        /// Show the number of the frame at the current position.
+        pub fn set_frame(&mut self, frame: Option<u64>) -> Action {
+            let text = Self::frame_text(frame);
+            if self.frame.text().text() == &text {
+                return Action::empty();
+            }
+            self.frame.set_text(text)
+        }
+
+        /// Disable the slider for media which can't be seeked in, showing whether it is live.
+        pub fn set_seekable(&mut self, mgr: &mut EventMgr, seekable: bool, live: bool) {
+            self.live = live;
+            if seekable != self.seekable {
+                self.seekable = seekable;
+                mgr.set_disabled(self.slider.id(), !seekable);
+            }
+        }
+
+        fn target(&self, fraction: f64) -> Option<Duration> {
+            self.duration.map(|d| d.mul_f64(fraction))
+        }
+
+        /// Update with the polled pipeline position.
+        ///
+        /// Ignored while the user is dragging the slider.
+        pub fn set_position(&mut self, position: Option<Duration>, duration: Option<Duration>) -> Action {
+            self.duration = duration;
+            if self.scrubbing {
+                return Action::empty();
+            }
+            let mut action = self.set_time_text(position);
+            if let (Some(p), Some(d)) = (position, duration) {
+                if !d.is_zero() {
+                    action |= self.slider.set_value(p.as_secs_f64() / d.as_secs_f64());
+                }
+            }
+            action
+        }
+    }
+
+    impl Widget for Self {
+        fn handle_event(&mut self, mgr: &mut EventMgr, event: Event) -> Response {
+            match event {
+                Event::TimerUpdate(TIMER_SETTLE) => {
+                    self.scrubbing = false;
+                    self.last_scrub = None;
+                    if let Some(target) = self.target(self.slider.value()) {
+                        mgr.push(GlobalMsg::Seek(Position::Time(target)));
+                    }
+                    Response::Used
+                }
+                _ => Response::Unused,
+            }
+        }
+
+        fn handle_message(&mut self, mgr: &mut EventMgr) {
+            if let Some(Msg::Move(v)) = mgr.try_pop::<Msg>() {
+                let Some(target) = self.target(v) else {
+                    return;
+                };
+                self.scrubbing = true;
+                *mgr |= self.set_time_text(Some(target));
+                if self.last_scrub.is_none_or(|t| t.elapsed() >= SCRUB_INTERVAL) {
+                    self.last_scrub = Some(Instant::now());
+                    mgr.push(GlobalMsg::Scrub(Position::Time(target)));
+                }
+                mgr.request_update(self.id(), TIMER_SETTLE, SETTLE_DELAY, false);
+            }
+        }
+    }
+}
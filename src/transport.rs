@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use kas::prelude::*;
+use kas::widgets::{EditBox, EditField, EditGuard, Label, TextButton};
+
+use super::video::{PlaybackState, Position, Repeat};
+use super::GlobalMsg;
+
+#[derive(Clone, Debug)]
+enum Msg {
+    Go,
+}
+
+/// Seeks to the position typed into the edit box, see [`Position`]'s `FromStr`.
+#[derive(Clone, Debug)]
+struct PositionGuard;
+impl EditGuard for PositionGuard {
+    fn activate(edit: &mut EditField<Self>, mgr: &mut EventMgr) -> Response {
+        match edit.get_str().parse::<Position>() {
+            Ok(pos) => {
+                edit.set_error_state(false);
+                mgr.push(GlobalMsg::Seek(pos));
+            }
+            Err(_) => {
+                edit.set_error_state(true);
+            }
+        }
+        Response::Used
+    }
+    fn edit(edit: &mut EditField<Self>, _mgr: &mut EventMgr) {
+        edit.set_error_state(false);
+    }
+    // the player's single-key shortcuts would fire while typing a position
+    fn focus_gained(edit: &mut EditField<Self>, mgr: &mut EventMgr) {
+        mgr.enable_alt_bypass(edit.id_ref(), false);
+    }
+    fn focus_lost(edit: &mut EditField<Self>, mgr: &mut EventMgr) {
+        mgr.enable_alt_bypass(edit.id_ref(), true);
+    }
+}
+
+impl_scope! {
+    #[widget{
+        layout = row: [
+            self.step_backward,
+            self.play,
+            self.pause,
+            self.stop,
+            self.step_forward,
+            self.mark_in,
+            self.mark_out,
+            self.state,
+            self.rate,
+            self.repeat,
+            self.position,
+            self.go,
+        ];
+    }]
+    #[derive(Debug)]
+    pub struct Transport {
+        core: widget_core!(),
+        #[widget] step_backward: TextButton,
+        #[widget] play: TextButton,
+        #[widget] pause: TextButton,
+        #[widget] stop: TextButton,
+        #[widget] step_forward: TextButton,
+        #[widget] state: Label<String>,
+        #[widget] rate: Label<String>,
+        #[widget] mark_in: TextButton,
+        #[widget] mark_out: TextButton,
+        #[widget] repeat: Label<String>,
+        #[widget] position: EditBox<PositionGuard>,
+        #[widget] go: TextButton,
+        seekable: bool,
+    }
+
+    impl Self {
+        pub fn new() -> Self {
+            Self {
+                core: Default::default(),
+                step_backward: TextButton::new_msg("<|", GlobalMsg::StepBackward),
+                play: TextButton::new_msg("Pla&y", GlobalMsg::Resume),
+                pause: TextButton::new_msg("P&ause", GlobalMsg::Pause),
+                stop: TextButton::new_msg("&Stop", GlobalMsg::Stop),
+                step_forward: TextButton::new_msg("|>", GlobalMsg::StepForward),
+                state: Label::new("no movie".to_string()),
+                rate: Label::new(String::new()),
+                mark_in: TextButton::new_msg("&In", GlobalMsg::MarkIn),
+                mark_out: TextButton::new_msg("&Out", GlobalMsg::MarkOut),
+                repeat: Label::new(String::new()),
+                position: EditBox::new("0:00").with_guard(PositionGuard).with_width_em(6.0, 8.0),
+                go: TextButton::new_msg("&Go", Msg::Go),
+                seekable: true,
+            }
+        }
+
+        /// Show the playback state, `None` when no movie is loaded.
+        pub fn set_state(&mut self, state: Option<PlaybackState>) -> Action {
+            let text = match state {
+                None => "no movie".to_string(),
+                Some(state) => state.to_string(),
+            };
+            self.state.set_text(text)
+        }
+
+        /// Show the playback rate, hidden at the normal rate.
+        pub fn set_rate(&mut self, rate: f64) -> Action {
+            let text = if rate == 1.0 { String::new() } else { format!("{rate}x") };
+            if self.rate.text().text() == &text {
+                return Action::empty();
+            }
+            self.rate.set_text(text)
+        }
+
+        /// Disable the controls which seek, for media which can't be seeked in.
+        pub fn set_seekable(&mut self, mgr: &mut EventMgr, seekable: bool) {
+            if seekable == self.seekable {
+                return;
+            }
+            self.seekable = seekable;
+            let ids = [
+                self.step_backward.id(),
+                self.step_forward.id(),
+                self.mark_in.id(),
+                self.mark_out.id(),
+                self.position.id(),
+                self.go.id(),
+            ];
+            for id in ids {
+                mgr.set_disabled(id, !seekable);
+            }
+        }
+
+        /// Show the repeat mode, or the in point waiting for its out point.
+        pub fn set_repeat(&mut self, repeat: Repeat, mark_in: Option<Duration>) -> Action {
+            let text = match (repeat, mark_in) {
+                (Repeat::Range(..), _) => "A-B".to_string(),
+                (_, Some(_)) => "A-".to_string(),
+                (Repeat::All, None) => "loop".to_string(),
+                (Repeat::Off, None) => String::new(),
+            };
+            if self.repeat.text().text() == &text {
+                return Action::empty();
+            }
+            self.repeat.set_text(text)
+        }
+    }
+
+    impl Widget for Self {
+        fn handle_message(&mut self, mgr: &mut EventMgr) {
+            if let Some(Msg::Go) = mgr.try_pop::<Msg>() {
+                let _ = PositionGuard::activate(&mut self.position, mgr);
+            }
+        }
+    }
+}
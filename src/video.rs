@@ -1,10 +1,11 @@
 use gst::prelude::*;
 use gstreamer as gst;
 use gstreamer_app as gst_app;
+use gstreamer_video as gst_video;
 //use iced::{image as img, Command, Image, Subscription};
 use log::{error, info};
 use num_traits::ToPrimitive;
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 //use std::time::Duration;
 use thiserror::Error;
 
@@ -28,6 +29,55 @@ impl From<Position> for gst::GenericFormattedValue {
     }
 }
 
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Position::Time(t) => {
+                let secs = t.as_secs();
+                write!(
+                    f,
+                    "{}:{:02}:{:02}.{:03}",
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60,
+                    t.subsec_millis()
+                )
+            }
+            Position::Frame(n) => write!(f, "#{n}"),
+        }
+    }
+}
+
+/// Parses `#123` as a frame and `[[h:]m:]s[.fraction]` as a time.
+impl std::str::FromStr for Position {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || Error::Position(s.to_string());
+        let s = s.trim();
+        if let Some(frame) = s.strip_prefix('#') {
+            return frame
+                .trim()
+                .parse::<u64>()
+                .map(Position::Frame)
+                .map_err(|_| err());
+        }
+        let mut secs = 0f64;
+        for (i, part) in s.split(':').enumerate() {
+            if i > 2 {
+                return Err(err());
+            }
+            let v = part.trim().parse::<f64>().map_err(|_| err())?;
+            if !v.is_finite() || v < 0.0 {
+                return Err(err());
+            }
+            secs = secs * 60.0 + v;
+        }
+        // finite values can still be too large for a duration
+        let time = std::time::Duration::try_from_secs_f64(secs).map_err(|_| err())?;
+        Ok(Position::Time(time))
+    }
+}
+
 impl From<std::time::Duration> for Position {
     fn from(t: std::time::Duration) -> Self {
         Position::Time(t)
@@ -54,14 +104,57 @@ pub enum Error {
     Caps,
     #[error("failed to query media duration or position")]
     Duration,
+    #[error("invalid position: {0}")]
+    Position(String),
+    #[error("failed to step a frame")]
+    Step,
+    #[error("invalid playback rate: {0}")]
+    Rate(f64),
+    #[error("no such track: {0}")]
+    Track(usize),
+}
+
+/// Playback state of a [`Streamer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackState {
+    /// Not started yet, or stopped by [`Streamer::stop`].
+    Stopped,
+    Paused,
+    Playing,
+}
+
+impl From<gst::State> for PlaybackState {
+    fn from(state: gst::State) -> Self {
+        match state {
+            gst::State::Playing => PlaybackState::Playing,
+            gst::State::Paused => PlaybackState::Paused,
+            _ => PlaybackState::Stopped,
+        }
+    }
+}
+
+impl std::fmt::Display for PlaybackState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlaybackState::Stopped => write!(f, "stopped"),
+            PlaybackState::Paused => write!(f, "paused"),
+            PlaybackState::Playing => write!(f, "playing"),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum VideoMessage {
     GstMessage(gst::Message),
-    NewSample(Vec<u8>, u32, u32),
+    /// A decoded frame, sized by the caps of its own sample.
+    NewSample(Frame),
+    /// The pipeline switched to the uri given to [`Streamer::set_next_uri`] without a gap.
+    ///
+    /// Sent when its stream starts, once the previous one is played out.
+    UriChanged(url::Url),
 }
 
+/// RGBA pixels of a frame, rows packed.
 #[derive(Default, Debug)]
 pub struct Frame {
     pub width: u32,
@@ -69,15 +162,239 @@ pub struct Frame {
     pub data: Vec<u8>,
 }
 
+/// Repeat mode of a [`Streamer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    Off,
+    /// Restart the media at its end.
+    All,
+    /// Loop seamlessly between an in and an out point (A-B repeat).
+    Range(std::time::Duration, std::time::Duration),
+}
+
+/// An audio or subtitle stream of the media, described by its tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Track {
+    pub language: Option<String>,
+    pub codec: Option<String>,
+}
+
+impl Track {
+    fn from_tags(tags: &gst::TagList) -> Self {
+        let language = tags
+            .get::<gst::tags::LanguageName>()
+            .or_else(|| tags.get::<gst::tags::LanguageCode>())
+            .map(|v| v.get().to_string());
+        let codec = tags
+            .get::<gst::tags::AudioCodec>()
+            .or_else(|| tags.get::<gst::tags::SubtitleCodec>())
+            .or_else(|| tags.get::<gst::tags::Codec>())
+            .map(|v| v.get().to_string());
+        Track { language, codec }
+    }
+}
+
+impl std::fmt::Display for Track {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.language, &self.codec) {
+            (Some(language), Some(codec)) => write!(f, "{language} ({codec})"),
+            (Some(language), None) => write!(f, "{language}"),
+            (None, Some(codec)) => write!(f, "{codec}"),
+            (None, None) => write!(f, "unknown"),
+        }
+    }
+}
+
+// Shared with the bus watch, which restarts the repeated range, and with the `about-to-finish`
+// handler, which switches to the next uri.
+#[derive(Debug, Clone)]
+struct Playback {
+    rate: f64,
+    repeat: Repeat,
+    uri: url::Url,
+    next_uri: Option<url::Url>,
+    // given to playbin by `about-to-finish`, playing from the next stream start
+    pending_uri: Option<url::Url>,
+}
+
+/// Seek back to the start of the repeated range; false when not repeating.
+///
+/// With `SeekFlags::SEGMENT` the pipeline posts `SegmentDone` instead of `Eos` at the end of the
+/// range, and a non-flushing seek from there continues the playback without a gap.
+fn restart(pipeline: &gst::Bin, playback: &Mutex<Playback>, flush: bool) -> bool {
+    let (rate, repeat) = {
+        let playback = playback.lock().unwrap();
+        (playback.rate, playback.repeat)
+    };
+    let (start, stop) = match repeat {
+        Repeat::Off => return false,
+        Repeat::All => (std::time::Duration::ZERO, None),
+        Repeat::Range(a, b) => (a, Some(b)),
+    };
+    let mut flags = gst::SeekFlags::SEGMENT | gst::SeekFlags::ACCURATE;
+    if flush {
+        flags |= gst::SeekFlags::FLUSH;
+    }
+    let to_clock_time = |t: std::time::Duration| gst::ClockTime::from_nseconds(t.as_nanos() as _);
+    let res = pipeline.seek(
+        rate,
+        flags,
+        gst::SeekType::Set,
+        to_clock_time(start),
+        gst::SeekType::Set,
+        stop.map(to_clock_time),
+    );
+    if let Err(e) = res {
+        error!("failed to restart: {e}");
+        return false;
+    }
+    true
+}
+
+// one of playbin's GstPlayFlags, by nick
+fn set_play_flag(playbin: &impl IsA<gst::Element>, nick: &str, on: bool) {
+    let flags = playbin.property_value("flags");
+    let Some(class) = gst::glib::FlagsClass::new(flags.type_()) else {
+        return;
+    };
+    let flags = if on {
+        class.set_by_nick(flags, nick)
+    } else {
+        class.unset_by_nick(flags, nick)
+    };
+    match flags {
+        Ok(flags) => playbin.set_property_from_value("flags", &flags),
+        Err(_) => error!("unknown play flag: {nick}"),
+    }
+}
+
+/// An element of the registry which draws audio as video.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Visualizer {
+    /// Factory name, as in [`Config::visualization`].
+    pub name: String,
+    pub longname: String,
+}
+
+/// The visualizers installed, sorted by their long name.
+pub fn visualizers() -> Vec<Visualizer> {
+    if let Err(e) = gst::init() {
+        error!("{e}");
+        return vec![];
+    }
+    let mut found: Vec<_> = gst::Registry::get()
+        .features(gst::ElementFactory::static_type())
+        .into_iter()
+        .filter_map(|f| f.downcast::<gst::ElementFactory>().ok())
+        .filter(|f| f.klass().contains("Visualization"))
+        .map(|f| Visualizer {
+            name: f.name().to_string(),
+            longname: f.longname().to_string(),
+        })
+        .collect();
+    found.sort_by(|a, b| a.longname.cmp(&b.longname));
+    found
+}
+
+/// Copy the pixels of an RGBA frame with its rows packed, as the pixmaps take them.
+///
+/// The frame layout comes from the video meta when upstream sets one, so rows may be padded or
+/// the plane may start at an offset into the buffer.
+fn pack_rgba(frame: &gst_video::VideoFrameRef<&gst::BufferRef>) -> Option<Vec<u8>> {
+    let row = frame.width() as usize * 4;
+    let height = frame.height() as usize;
+    let stride = usize::try_from(*frame.plane_stride().first()?).ok()?;
+    let data = frame.plane_data(0).ok()?;
+    if stride < row || data.len() < stride * (height.max(1) - 1) + row {
+        return None;
+    }
+    if stride == row {
+        return Some(data[..row * height].to_vec());
+    }
+    let mut packed = Vec::with_capacity(row * height);
+    for y in 0..height {
+        packed.extend_from_slice(&data[y * stride..y * stride + row]);
+    }
+    Some(packed)
+}
+
+/// Options fixed when a [`Streamer`] is created.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Synchronize the frames to the pipeline clock.
+    pub sync: bool,
+    /// Keep the audio pitch at rates other than 1.0 with `scaletempo`.
+    pub scaletempo: bool,
+    /// Factory name of the visualizer drawn for media without video, see [`visualizers`].
+    pub visualization: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            sync: true,
+            scaletempo: true,
+            visualization: None,
+        }
+    }
+}
+
+/// Frames per second of a caps framerate, `None` for the 0/1 of variable rates.
+fn fraction_to_f64(f: gst::Fraction) -> Option<f64> {
+    if f.numer() <= 0 || f.denom() <= 0 {
+        return None;
+    }
+    num_rational::Rational32::new(f.numer(), f.denom()).to_f64()
+}
+
+/// Frame rate assumed without frames, or until variable rate frames tell theirs.
+const FALLBACK_FRAMERATE: f64 = 25.0;
+
+/// Interval between frames, measured from their timestamps by the app sink callback.
+///
+/// Variable frame rate media announce a framerate of 0/1, or none at all.
+#[derive(Debug, Default)]
+struct Pacing {
+    last_pts: Option<gst::ClockTime>,
+    /// Moving average of the intervals.
+    interval: Option<std::time::Duration>,
+}
+
+impl Pacing {
+    fn update(&mut self, pts: Option<gst::ClockTime>) {
+        let Some(pts) = pts else {
+            return;
+        };
+        if let Some(last) = self.last_pts {
+            // gaps are seeks, or backward playback
+            let delta = pts
+                .checked_sub(last)
+                .map(|d| std::time::Duration::from_nanos(d.nseconds()));
+            if let Some(delta) = delta.filter(|d| !d.is_zero() && d.as_secs() < 1) {
+                self.interval = Some(match self.interval {
+                    Some(interval) => interval.mul_f64(0.9) + delta.mul_f64(0.1),
+                    None => delta,
+                });
+            }
+        }
+        self.last_pts = Some(pts);
+    }
+}
+
 /// Video player which handles multimedia playback.
 pub struct Streamer {
     do_sync: bool,
     pipeline: gst::Bin,
     app_sink: gst_app::AppSink,
-    width: u32,
-    height: u32,
-    framerate: f64,
-    duration: std::time::Duration,
+    has_video: bool,
+    // of the latest frame, which changes with adaptive streams and concatenated files
+    size: Arc<Mutex<(u32, u32)>>,
+    /// Framerate of the caps of the latest frame, `None` when variable or unknown.
+    framerate: Arc<Mutex<Option<f64>>>,
+    pacing: Arc<Mutex<Pacing>>,
+    duration: Option<std::time::Duration>,
+    live: bool,
+    playback: Arc<Mutex<Playback>>,
     msg_sender: mpsc::SyncSender<VideoMessage>,
     msg_receiver: Option<mpsc::Receiver<VideoMessage>>,
 }
@@ -98,7 +415,7 @@ impl std::fmt::Debug for Streamer {
 }
 
 impl Streamer {
-    pub fn new(uri: &url::Url, sync: bool) -> Result<Self, Error> {
+    pub fn new(uri: &url::Url, config: &Config) -> Result<Self, Error> {
         gst::init()?;
         let (msg_sender, msg_receiver) = std::sync::mpsc::sync_channel::<VideoMessage>(10);
 
@@ -112,50 +429,83 @@ impl Streamer {
             .downcast::<gst_app::AppSink>()
             .unwrap();
 
+        if config.scaletempo {
+            match gst::ElementFactory::make("scaletempo").build() {
+                Ok(scaletempo) => pipeline.set_property("audio-filter", &scaletempo),
+                Err(e) => error!("scaletempo is not available: {e}"),
+            }
+        }
+
+        // playbin only draws it when there is no video stream
+        if let Some(ref name) = config.visualization {
+            match gst::ElementFactory::make(name).build() {
+                Ok(vis) => {
+                    pipeline.set_property("vis-plugin", &vis);
+                    set_play_flag(&pipeline, "vis", true);
+                }
+                Err(e) => error!("visualizer {name} is not available: {e}"),
+            }
+        }
+
         app_sink.set_sync(false);
         pipeline.set_state(gst::State::Playing)?;
         // wait for up to 5 seconds until the decoder gets the source capabilities
-        pipeline.state(gst::ClockTime::from_seconds(5)).0?;
-        pipeline.set_state(gst::State::Paused)?;
+        let waited = pipeline.state(gst::ClockTime::from_seconds(5)).0?;
+        let paused = pipeline.set_state(gst::State::Paused)?;
+        // live sources don't preroll, they only produce data while playing
+        let live = waited == gst::StateChangeSuccess::NoPreroll
+            || paused == gst::StateChangeSuccess::NoPreroll;
+        info!("live={live}");
 
         // extract resolution and framerate
+        let has_video = pipeline.property::<i32>("n-video") > 0;
         let pads = app_sink.sink_pads();
         let pad = pads.get(0).unwrap();
 
-        let caps = pad.current_caps().ok_or(Error::Caps)?;
-        let s = caps.structure(0).ok_or(Error::Caps)?;
-        let width = s.get::<i32>("width").map_err(|_| Error::Caps)?;
-        let height = s.get::<i32>("height").map_err(|_| Error::Caps)?;
-        let width = u32::try_from(width).map_err(|_| Error::Caps)?;
-        let height = u32::try_from(height).map_err(|_| Error::Caps)?;
+        // without video the app sink gets caps only from a visualizer
+        let (width, height, framerate) = match pad.current_caps() {
+            Some(caps) => {
+                let s = caps.structure(0).ok_or(Error::Caps)?;
+                let width = s.get::<i32>("width").map_err(|_| Error::Caps)?;
+                let height = s.get::<i32>("height").map_err(|_| Error::Caps)?;
+                let width = u32::try_from(width).map_err(|_| Error::Caps)?;
+                let height = u32::try_from(height).map_err(|_| Error::Caps)?;
+                let framerate = s
+                    .get::<gst::Fraction>("framerate")
+                    .ok()
+                    .and_then(fraction_to_f64);
+                (width, height, framerate)
+            }
+            None if !has_video => (0, 0, Some(FALLBACK_FRAMERATE)),
+            None => return Err(Error::Caps),
+        };
         info!("width={width}, height={height}");
-        let framerate = s
-            .get::<gst::Fraction>("framerate")
-            .map_err(|_| Error::Caps)?;
-        info!("framerate={framerate}");
-
-        let duration = std::time::Duration::from_nanos(
-            pipeline
-                .query_duration::<gst::ClockTime>()
-                .ok_or(Error::Duration)?
-                .nseconds(),
-        );
+        info!("framerate={framerate:?}");
+
+        let duration = pipeline
+            .query_duration::<gst::ClockTime>()
+            .map(|d| std::time::Duration::from_nanos(d.nseconds()));
         info!("duration={:?}", duration);
 
         Ok(Streamer {
-            do_sync: sync,
+            do_sync: config.sync,
             pipeline: pipeline.downcast::<gst::Bin>().unwrap(),
             app_sink: app_sink,
+            has_video,
             msg_sender: msg_sender,
             msg_receiver: Some(msg_receiver),
-            width,
-            height,
-            framerate: num_rational::Rational32::new(
-                    framerate.numer() as _,
-                    framerate.denom() as _,
-                )
-                .to_f64().unwrap(/* if the video framerate is bad then it would've been implicitly caught far earlier */),
+            size: Arc::new(Mutex::new((width, height))),
+            framerate: Arc::new(Mutex::new(framerate)),
+            pacing: Default::default(),
             duration,
+            live,
+            playback: Arc::new(Mutex::new(Playback {
+                rate: 1.0,
+                repeat: Repeat::Off,
+                uri: uri.clone(),
+                next_uri: None,
+                pending_uri: None,
+            })),
         })
     }
 
@@ -170,14 +520,22 @@ impl Streamer {
         if true {
             let msg_sender_sink = self.msg_sender.clone();
             let main_loop_ref = main_loop.clone();
+            let pipeline_weak = self.pipeline.downgrade();
+            let playback = self.playback.clone();
             let bus = self.pipeline.bus().unwrap();
             let _ = bus.remove_watch();
             let _ = bus.add_watch(move |_bus, msg| match msg.view() {
                 gst::MessageView::Eos(_) => {
                     error!("gst.Eos");
+                    // the first end of a repeated media, later ones come as SegmentDone
+                    if let Some(pipeline) = pipeline_weak.upgrade() {
+                        if restart(&pipeline, &playback, true) {
+                            return gst::glib::source::Continue(true);
+                        }
+                    }
+                    // kept watching, playing or seeking again after the end decodes again
                     let _ = msg_sender_sink.send(VideoMessage::GstMessage(msg.clone()));
-                    main_loop_ref.quit();
-                    gst::glib::source::Continue(false)
+                    gst::glib::source::Continue(true)
                 }
                 gst::MessageView::Error(_) => {
                     error!("gst.Error");
@@ -185,7 +543,30 @@ impl Streamer {
                     main_loop_ref.quit();
                     gst::glib::source::Continue(false)
                 }
-                gst::MessageView::StateChanged(..) => gst::glib::source::Continue(true),
+                gst::MessageView::StateChanged(..) => {
+                    // only the top-level pipeline is interesting for the ui
+                    if msg.src().is_some_and(|src| src.is::<gst::Pipeline>()) {
+                        let _ = msg_sender_sink.send(VideoMessage::GstMessage(msg.clone()));
+                    }
+                    gst::glib::source::Continue(true)
+                }
+                gst::MessageView::SegmentDone(..) => {
+                    if let Some(pipeline) = pipeline_weak.upgrade() {
+                        restart(&pipeline, &playback, false);
+                    }
+                    gst::glib::source::Continue(true)
+                }
+                gst::MessageView::StreamStart(..) | gst::MessageView::DurationChanged(..) => {
+                    if let gst::MessageView::StreamStart(..) = msg.view() {
+                        let mut playback = playback.lock().unwrap();
+                        if let Some(next) = playback.pending_uri.take() {
+                            playback.uri = next.clone();
+                            let _ = msg_sender_sink.send(VideoMessage::UriChanged(next));
+                        }
+                    }
+                    let _ = msg_sender_sink.send(VideoMessage::GstMessage(msg.clone()));
+                    gst::glib::source::Continue(true)
+                }
                 gst::MessageView::Tag(..) => gst::glib::source::Continue(true),
                 m => {
                     error!("gst.other: {:?}", m);
@@ -193,36 +574,71 @@ impl Streamer {
                 }
             });
         }
+        if true {
+            let playback = self.playback.clone();
+            self.pipeline
+                .connect("about-to-finish", false, move |args| {
+                    let playbin = args[0].get::<gst::Element>().ok()?;
+                    let next = {
+                        let mut playback = playback.lock().unwrap();
+                        if playback.repeat != Repeat::Off {
+                            return None;
+                        }
+                        let next = playback.next_uri.take()?;
+                        playback.pending_uri = Some(next.clone());
+                        next
+                    };
+                    info!("continue with {next}");
+                    playbin.set_property("uri", next.as_str());
+                    None
+                });
+        }
         if true {
             let msg_sender_sink = self.msg_sender.clone();
+            let pacing = self.pacing.clone();
+            let size = self.size.clone();
+            let framerate = self.framerate.clone();
+            let send_frame =
+                move |sample: gst::Sample| -> Result<gst::FlowSuccess, gst::FlowError> {
+                    let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
+                    // prerolls too, the steps while paused rely on the timestamp of the shown frame
+                    pacing.lock().unwrap().update(buffer.pts());
+                    let caps = sample.caps().ok_or(gst::FlowError::Error)?;
+                    let info =
+                        gst_video::VideoInfo::from_caps(caps).map_err(|_| gst::FlowError::Error)?;
+                    let frame = gst_video::VideoFrameRef::from_buffer_ref_readable(buffer, &info)
+                        .map_err(|_| gst::FlowError::Error)?;
+                    let width = frame.width();
+                    let height = frame.height();
+                    *size.lock().unwrap() = (width, height);
+                    *framerate.lock().unwrap() = fraction_to_f64(info.fps());
+
+                    let data = pack_rgba(&frame).ok_or(gst::FlowError::Error)?;
+                    let _ = msg_sender_sink.send(VideoMessage::NewSample(Frame {
+                        width,
+                        height,
+                        data,
+                    }));
+                    Ok(gst::FlowSuccess::Ok)
+                };
+            let send_preroll = send_frame.clone();
             self.app_sink.set_callbacks(
                 gst_app::AppSinkCallbacks::builder()
                     .new_sample(move |sink| {
                         let sample = sink.pull_sample().map_err(|_| gst::FlowError::Eos)?; // it fires eos event
-
-                        let buffer = sample.buffer().ok_or(gst::FlowError::Error)?;
-                        let bufmap = buffer.map_readable().map_err(|_| gst::FlowError::Error)?;
-
-                        let pad = sink.static_pad("sink").ok_or(gst::FlowError::Error)?;
-
-                        let caps = pad.current_caps().ok_or(gst::FlowError::Error)?;
-                        let s = caps.structure(0).ok_or(gst::FlowError::Error)?;
-                        let width = s.get::<i32>("width").map_err(|_| gst::FlowError::Error)?;
-                        let height = s.get::<i32>("height").map_err(|_| gst::FlowError::Error)?;
-                        let width = u32::try_from(width).map_err(|_| gst::FlowError::Error)?;
-                        let height = u32::try_from(height).map_err(|_| gst::FlowError::Error)?;
-
-                        let senddata = bufmap.to_vec();
-                        let _ =
-                            msg_sender_sink.send(VideoMessage::NewSample(senddata, width, height));
-                        Ok(gst::FlowSuccess::Ok)
+                        send_frame(sample)
+                    })
+                    // frames shown while paused, after a step or a flushing seek, only come as prerolls
+                    .new_preroll(move |sink| {
+                        let sample = sink.pull_preroll().map_err(|_| gst::FlowError::Eos)?;
+                        send_preroll(sample)
                     })
                     .build(),
             );
         }
 
         let _ = self.pipeline.seek(
-            1.0f64,
+            self.rate(),
             gst::SeekFlags::FLUSH,
             gst::SeekType::Set,
             gst::format::Bytes::ZERO,
@@ -238,21 +654,452 @@ impl Streamer {
         });
     }
 
+    /// Pause the playback, keeping the current position.
+    pub fn pause(&mut self) -> Result<(), Error> {
+        self.pipeline.set_state(gst::State::Paused)?;
+        Ok(())
+    }
+
+    /// Resume the playback after [`Streamer::pause`] or [`Streamer::stop`].
+    ///
+    /// After a stop the media is played again from the beginning.
+    pub fn resume(&mut self) -> Result<(), Error> {
+        self.pipeline.set_state(gst::State::Playing)?;
+        Ok(())
+    }
+
+    /// Stop the playback and release the decoders.
+    pub fn stop(&mut self) -> Result<(), Error> {
+        self.pipeline.set_state(gst::State::Ready)?;
+        Ok(())
+    }
+
+    /// Seek to `position`, keeping the current playback rate.
+    ///
+    /// Unless `accurate`, the playback continues from the nearest key frame, which is faster.
+    pub fn seek(&mut self, position: Position, accurate: bool) -> Result<(), Error> {
+        let flags = gst::SeekFlags::FLUSH
+            | if accurate {
+                gst::SeekFlags::ACCURATE
+            } else {
+                gst::SeekFlags::KEY_UNIT
+            };
+        match (self.seek_with_rate(self.rate(), flags, position), position) {
+            // not every demuxer can seek in frames, so retry with the time of the frame
+            (Err(e), Position::Frame(n)) => {
+                info!("frame seek failed ({e}), retrying by time");
+                let t = self.frame_to_time(n);
+                self.seek_with_rate(self.rate(), flags, Position::Time(t))?;
+            }
+            (res, _) => res?,
+        }
+        Ok(())
+    }
+
+    /// Change the playback rate, continuing from the current position.
+    ///
+    /// Rates below 1.0 play in slow motion, negative rates play backward.
+    pub fn set_rate(&mut self, rate: f64) -> Result<(), Error> {
+        if rate == 0.0 || !rate.is_finite() {
+            return Err(Error::Rate(rate));
+        }
+        let position = self.position().ok_or(Error::Duration)?;
+        self.seek_with_rate(
+            rate,
+            gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+            Position::Time(position),
+        )?;
+        self.playback.lock().unwrap().rate = rate;
+        Ok(())
+    }
+
+    /// The uri being played.
+    pub fn uri(&self) -> url::Url {
+        self.playback.lock().unwrap().uri.clone()
+    }
+
+    /// Set the uri to continue with, without a gap, when the current one ends.
+    pub fn set_next_uri(&mut self, uri: Option<url::Url>) {
+        self.playback.lock().unwrap().next_uri = uri;
+    }
+
+    /// Query the duration again, e.g. after [`VideoMessage::UriChanged`].
+    ///
+    /// The last known duration is kept when the query fails.
+    pub fn update_duration(&mut self) {
+        if let Some(duration) = self.pipeline.query_duration::<gst::ClockTime>() {
+            self.duration = Some(std::time::Duration::from_nanos(duration.nseconds()));
+        }
+    }
+
     #[inline(always)]
-    #[allow(dead_code)]
+    pub fn rate(&self) -> f64 {
+        self.playback.lock().unwrap().rate
+    }
+
+    /// Set the repeat mode.
+    ///
+    /// An A-B range starts over from its in point.
+    pub fn set_repeat(&mut self, repeat: Repeat) -> Result<(), Error> {
+        self.playback.lock().unwrap().repeat = repeat;
+        let rate = self.rate();
+        let position = match repeat {
+            Repeat::Range(a, _) if rate > 0.0 => a,
+            Repeat::Range(_, b) => b,
+            // not prerolled yet, the bus watch will still restart at the end
+            _ => match self.position() {
+                Some(position) => position,
+                None => return Ok(()),
+            },
+        };
+        // re-seek to apply or remove the segment flag and the out point
+        self.seek_with_rate(
+            rate,
+            gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+            Position::Time(position),
+        )?;
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn repeat(&self) -> Repeat {
+        self.playback.lock().unwrap().repeat
+    }
+
+    // A backward playback runs from `position` down to the start of the media, or of the
+    // repeated range.
+    fn seek_with_rate(
+        &self,
+        rate: f64,
+        flags: gst::SeekFlags,
+        position: Position,
+    ) -> Result<(), gst::glib::BoolError> {
+        let (flags, range) = match self.repeat() {
+            Repeat::Off => (flags, None),
+            Repeat::All => (flags | gst::SeekFlags::SEGMENT, None),
+            Repeat::Range(a, b) => (flags | gst::SeekFlags::SEGMENT, Some((a, b))),
+        };
+        let position = match (position, range) {
+            // the range is in time, so both ends of the seek must be too, and a start out of the
+            // range would come after its stop
+            (Position::Frame(n), Some((a, b))) => Position::Time(self.frame_to_time(n).clamp(a, b)),
+            (Position::Time(t), Some((a, b))) => Position::Time(t.clamp(a, b)),
+            (p, None) => p,
+        };
+        let position = gst::GenericFormattedValue::from(position);
+        let format = position.format();
+        let (start, stop) = match range {
+            Some((a, b)) => (Position::Time(a).into(), Position::Time(b).into()),
+            None => (
+                gst::GenericFormattedValue::new(format, 0),
+                gst::GenericFormattedValue::none_for_format(format),
+            ),
+        };
+        let (start, stop) = if rate < 0.0 {
+            (start, position)
+        } else {
+            (position, stop)
+        };
+        self.pipeline.seek(
+            rate,
+            flags,
+            gst::SeekType::Set,
+            start,
+            gst::SeekType::Set,
+            stop,
+        )
+    }
+
+    /// Step one frame forward. The playback is paused first.
+    pub fn step_forward(&mut self) -> Result<(), Error> {
+        self.pause_and_wait()?;
+        let step = gst::event::Step::new(gst::format::Buffers::ONE, 1.0, true, false);
+        if !self.app_sink.send_event(step) {
+            return Err(Error::Step);
+        }
+        Ok(())
+    }
+
+    /// Step one frame backward. The playback is paused first.
+    ///
+    /// GStreamer can only step forward, so this is an accurate seek to the previous frame.
+    pub fn step_backward(&mut self) -> Result<(), Error> {
+        self.pause_and_wait()?;
+        if self.has_video && self.framerate.lock().unwrap().is_none() {
+            // frames don't fall on a grid, but the one before ends right where the shown one starts
+            let pts = self
+                .pacing
+                .lock()
+                .unwrap()
+                .last_pts
+                .ok_or(Error::Duration)?;
+            let Some(end) = pts.nseconds().checked_sub(1) else {
+                return Ok(());
+            };
+            return self.seek(Position::Time(std::time::Duration::from_nanos(end)), true);
+        }
+        let frame = self.current_frame().ok_or(Error::Duration)?;
+        if frame == 0 {
+            return Ok(());
+        }
+        self.seek(Position::Time(self.frame_to_time(frame - 1)), true)
+    }
+
+    fn pause_and_wait(&mut self) -> Result<(), Error> {
+        if self.state() != PlaybackState::Paused {
+            self.pause()?;
+            self.pipeline.state(gst::ClockTime::from_seconds(1)).0?;
+        }
+        Ok(())
+    }
+
+    /// Number of the frame at the current position, `None` without video.
+    pub fn current_frame(&self) -> Option<u64> {
+        if !self.has_video {
+            return None;
+        }
+        self.position().map(|t| self.time_to_frame(t))
+    }
+
+    // Frame start times are rounded up to whole nanoseconds, so a position exactly at the start
+    // of a frame must not be floored down into the previous frame.
+    //
+    // With a variable frame rate, frame numbers are estimated from the average rate.
+    fn time_to_frame(&self, t: std::time::Duration) -> u64 {
+        (t.as_secs_f64() * self.framerate() + 1e-6).floor() as u64
+    }
+
+    fn frame_to_time(&self, frame: u64) -> std::time::Duration {
+        std::time::Duration::from_nanos((frame as f64 * 1e9 / self.framerate()).ceil() as u64)
+    }
+
+    /// Current playback state.
+    ///
+    /// While a state change is in progress, the state being changed to is returned.
+    pub fn state(&self) -> PlaybackState {
+        let (_, current, pending) = self.pipeline.state(gst::ClockTime::ZERO);
+        match pending {
+            gst::State::VoidPending => current.into(),
+            pending => pending.into(),
+        }
+    }
+
+    /// Current playback position, `None` when the pipeline can't tell.
+    pub fn position(&self) -> Option<std::time::Duration> {
+        self.pipeline
+            .query_position::<gst::ClockTime>()
+            .map(|t| std::time::Duration::from_nanos(t.nseconds()))
+    }
+
+    /// Set the audio volume, linear from 0.0 (silent) to 1.0 (full).
+    pub fn set_volume(&mut self, volume: f64) {
+        self.pipeline.set_property("volume", volume.clamp(0.0, 1.0));
+    }
+
+    pub fn set_mute(&mut self, mute: bool) {
+        self.pipeline.set_property("mute", mute);
+    }
+
+    /// Delay the audio behind the video by `ms` milliseconds, negative to play it earlier.
+    pub fn set_audio_delay(&mut self, ms: i64) {
+        // playbin's av-offset is how far the audio is ahead of the video
+        self.pipeline.set_property("av-offset", -ms * 1_000_000);
+    }
+
+    /// Delay the embedded subtitles by `ms` milliseconds, negative to show them earlier.
+    pub fn set_subtitle_delay(&mut self, ms: i64) {
+        self.pipeline.set_property("text-offset", ms * 1_000_000);
+    }
+
+    fn tracks(&self, kind: &str) -> Vec<Track> {
+        let n = self.pipeline.property::<i32>(&format!("n-{kind}"));
+        (0..n)
+            .map(|i| {
+                self.pipeline
+                    .emit_by_name::<Option<gst::TagList>>(&format!("get-{kind}-tags"), &[&i])
+                    .map_or_else(Track::default, |tags| Track::from_tags(&tags))
+            })
+            .collect()
+    }
+
+    fn select_track(&mut self, kind: &str, index: usize) -> Result<(), Error> {
+        let n = self.pipeline.property::<i32>(&format!("n-{kind}"));
+        match i32::try_from(index) {
+            Ok(i) if i < n => {
+                self.pipeline.set_property(&format!("current-{kind}"), i);
+                Ok(())
+            }
+            _ => Err(Error::Track(index)),
+        }
+    }
+
+    fn play_flag(&self, nick: &str) -> bool {
+        let flags = self.pipeline.property_value("flags");
+        gst::glib::FlagsClass::new(flags.type_()).is_some_and(|c| c.is_set_by_nick(&flags, nick))
+    }
+
+    fn set_play_flag(&mut self, nick: &str, on: bool) {
+        set_play_flag(&self.pipeline, nick, on);
+    }
+
+    /// The audio streams of the media, in the order of their index.
+    pub fn audio_tracks(&self) -> Vec<Track> {
+        self.tracks("audio")
+    }
+
+    /// Index of the audio stream being played, see [`Streamer::audio_tracks`].
+    pub fn current_audio_track(&self) -> Option<usize> {
+        usize::try_from(self.pipeline.property::<i32>("current-audio")).ok()
+    }
+
+    pub fn select_audio_track(&mut self, index: usize) -> Result<(), Error> {
+        self.select_track("audio", index)
+    }
+
+    /// The embedded subtitle streams, in the order of their index.
+    pub fn text_tracks(&self) -> Vec<Track> {
+        self.tracks("text")
+    }
+
+    /// Index of the subtitle stream being shown, `None` when they are hidden.
+    pub fn current_text_track(&self) -> Option<usize> {
+        if !self.play_flag("text") {
+            return None;
+        }
+        usize::try_from(self.pipeline.property::<i32>("current-text")).ok()
+    }
+
+    /// Show the subtitle stream at `index`, or hide the subtitles.
+    ///
+    /// Playbin renders them into the frames before they reach the app sink.
+    pub fn select_text_track(&mut self, index: Option<usize>) -> Result<(), Error> {
+        match index {
+            Some(index) => {
+                self.select_track("text", index)?;
+                self.set_play_flag("text", true);
+            }
+            None => self.set_play_flag("text", false),
+        }
+        Ok(())
+    }
+
+    /// Query the streams again after [`VideoMessage::UriChanged`], the next media may have no video.
+    ///
+    /// The size and the framerate follow the frames, which only come with video or a visualizer.
+    pub fn update_video_info(&mut self) {
+        self.has_video = self.pipeline.property::<i32>("n-video") > 0;
+        *self.pacing.lock().unwrap() = Pacing::default();
+        if !self.has_video
+            && self
+                .pipeline
+                .property::<Option<gst::Element>>("vis-plugin")
+                .is_none()
+        {
+            *self.size.lock().unwrap() = (0, 0);
+            *self.framerate.lock().unwrap() = Some(FALLBACK_FRAMERATE);
+        }
+    }
+
+    /// Whether the media has a video stream, audio-only files have none.
+    pub fn has_video(&self) -> bool {
+        self.has_video
+    }
+
+    /// The encoded picture of the `image` tag, usually the cover art of an audio file.
+    pub fn cover_art(&self) -> Option<Vec<u8>> {
+        let tags = self
+            .pipeline
+            .emit_by_name::<Option<gst::TagList>>("get-audio-tags", &[&0i32])?;
+        let sample = tags
+            .get::<gst::tags::Image>()
+            .or_else(|| tags.get::<gst::tags::PreviewImage>())?
+            .get();
+        let map = sample.buffer()?.map_readable().ok()?;
+        Some(map.as_slice().to_vec())
+    }
+
+    /// Size of the latest frame, `(0, 0)` when no frames come.
     pub fn size(&self) -> (u32, u32) {
-        (self.width, self.height)
+        *self.size.lock().unwrap()
     }
 
+    /// Frames per second, measured from the frame timestamps when the caps don't tell it.
     #[inline(always)]
-    #[allow(dead_code)]
     pub fn framerate(&self) -> f64 {
-        self.framerate
+        let framerate = *self.framerate.lock().unwrap();
+        framerate.unwrap_or_else(|| {
+            let interval = self.pacing.lock().unwrap().interval;
+            interval.map_or(FALLBACK_FRAMERATE, |i| 1.0 / i.as_secs_f64())
+        })
+    }
+
+    /// Interval between two frames, see [`Streamer::framerate`].
+    pub fn frame_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(1.0 / self.framerate())
     }
 
+    /// Duration of the media, `None` for live sources and streams which don't tell it.
     #[inline(always)]
-    #[allow(dead_code)]
-    pub fn duration(&self) -> std::time::Duration {
+    pub fn duration(&self) -> Option<std::time::Duration> {
         self.duration
     }
+
+    /// Whether the source is live, e.g. a capture device or a broadcast.
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+
+    /// Whether the media can be seeked in, which live sources and many network streams can't.
+    pub fn seekable(&self) -> bool {
+        let mut query = gst::query::Seeking::new(gst::Format::Time);
+        self.pipeline.query(query.query_mut()) && query.result().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn parse(s: &str) -> Option<Position> {
+        s.parse().ok()
+    }
+
+    #[test]
+    fn parse_position() {
+        assert_eq!(parse("#12"), Some(Position::Frame(12)));
+        assert_eq!(parse(" # 7 "), Some(Position::Frame(7)));
+        assert_eq!(parse("42"), Some(Position::Time(Duration::from_secs(42))));
+        assert_eq!(
+            parse("1:02.5"),
+            Some(Position::Time(Duration::from_millis(62_500)))
+        );
+        assert_eq!(
+            parse("1:02:03.25"),
+            Some(Position::Time(Duration::from_millis(3_723_250)))
+        );
+        assert_eq!(
+            parse(&Position::Time(Duration::from_millis(3_723_250)).to_string()),
+            parse("1:02:03.25")
+        );
+    }
+
+    #[test]
+    fn reject_position() {
+        for s in [
+            "",
+            "#",
+            "#-1",
+            "-5",
+            "1:-2",
+            "1:2:3:4",
+            "a:b",
+            "inf",
+            "NaN",
+            "1e300",
+            "99999999999999999999",
+        ] {
+            assert!(parse(s).is_none(), "{s:?} should not parse");
+        }
+    }
 }